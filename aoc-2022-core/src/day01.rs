@@ -0,0 +1,70 @@
+use crate::{parse, Error, ErrorKind};
+
+// Returns (most calories carried by one elf, total carried by the top three)
+pub fn solve(input: &[u8]) -> Result<(u32, u32), Error> {
+    // Largest first
+    let mut top = [0_u32; 3];
+    let mut elves = 0;
+    let mut push = |total: u32| {
+        elves += 1;
+        if let Some(i) = top.iter().position(|&t| total > t) {
+            top[i..].rotate_right(1);
+            top[i] = total;
+        }
+    };
+
+    let mut acc: u32 = 0;
+    for (offset, l) in parse::lines(input) {
+        if l.is_empty() {
+            push(acc);
+            acc = 0;
+            continue;
+        }
+        acc = acc.checked_add(parse::uint(l, offset)?).ok_or(Error::new(
+            offset,
+            l.len(),
+            ErrorKind::Overflow,
+        ))?;
+    }
+    push(acc);
+
+    if elves < top.len() {
+        let kind = ErrorKind::TooFew {
+            what: "elves",
+            need: top.len(),
+            found: elves,
+        };
+        return Err(Error::new(0, 0, kind));
+    }
+    let top3 = top
+        .iter()
+        .try_fold(0_u32, |sum, t| sum.checked_add(*t))
+        .ok_or(Error::new(0, 0, ErrorKind::Overflow))?;
+    Ok((top[0], top3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let example = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+        assert_eq!(solve(example.as_bytes()), Ok((24000, 45000)));
+        assert_eq!(solve(b"1\n\n3\n\n2\n\n3"), Ok((3, 8)));
+
+        assert_eq!(
+            solve(b"1\n\n2").unwrap_err().kind,
+            ErrorKind::TooFew {
+                what: "elves",
+                need: 3,
+                found: 2
+            }
+        );
+        assert_eq!(solve(b"1\nx\n\n2\n\n3").unwrap_err().offset, 2);
+        assert_eq!(
+            solve(b"4294967295\n1\n\n1\n\n1").unwrap_err(),
+            Error::new(11, 1, ErrorKind::Overflow)
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{parse, Error, ErrorKind};
+
+// Rock, paper and scissors are 0, 1 and 2, so the winner is always one more (mod 3) than the loser.
+fn score(opponent: u32, mine: u32) -> u32 {
+    // 0 is a loss, 1 a draw and 2 a win
+    let outcome = (mine + 4 - opponent) % 3;
+    mine + 1 + outcome * 3
+}
+
+// Returns (total score reading XYZ as moves, total score reading XYZ as outcomes)
+pub fn solve(input: &[u8]) -> Result<(u32, u32), Error> {
+    let mut part1: u32 = 0;
+    let mut part2: u32 = 0;
+    for (offset, l) in parse::lines(input) {
+        let &[other @ b'A'..=b'C', b' ', mine @ b'X'..=b'Z'] = l else {
+            let kind = ErrorKind::Expected("a line like \"A X\" (A-C, then X-Z)");
+            return Err(Error::new(offset, l.len(), kind));
+        };
+        let (other, mine) = ((other - b'A') as u32, (mine - b'X') as u32);
+        let overflow = Error::new(offset, l.len(), ErrorKind::Overflow);
+        part1 = part1.checked_add(score(other, mine)).ok_or(overflow)?;
+        // mine is the outcome for part 2: lose by playing the one before, win with the one after
+        let for_outcome = (other + mine + 2) % 3;
+        part2 = part2
+            .checked_add(score(other, for_outcome))
+            .ok_or(overflow)?;
+    }
+    Ok((part1, part2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(b"A Y\nB X\nC Z"), Ok((15, 12)));
+        assert_eq!(solve(b""), Ok((0, 0)));
+        for bad in ["A Y\nD Z", "A Y\nB W", "A Y\nB  X", "A Y\nBX"] {
+            assert_eq!(solve(bad.as_bytes()).unwrap_err().offset, 4, "{bad:?}");
+        }
+    }
+}
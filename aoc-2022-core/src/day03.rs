@@ -0,0 +1,86 @@
+use crate::{parse, Error, ErrorKind};
+
+// Items are letters, each set of them one bit per priority (1-52)
+fn items(s: &[u8], offset: usize) -> Result<u64, Error> {
+    let mut set = 0;
+    for (i, &b) in s.iter().enumerate() {
+        let priority = match b {
+            b'a'..=b'z' => b - b'a' + 1,
+            b'A'..=b'Z' => b - b'A' + 27,
+            _ => return Err(Error::new(offset + i, 1, ErrorKind::Expected("a letter"))),
+        };
+        set |= 1 << priority;
+    }
+    Ok(set)
+}
+
+fn only_item(set: u64, offset: usize, len: usize) -> Result<u32, Error> {
+    if set.count_ones() != 1 {
+        return Err(Error::new(offset, len, ErrorKind::NoCommonItem));
+    }
+    Ok(set.trailing_zeros())
+}
+
+// Returns (sum of priorities of the item in both compartments, sum of each group's badge)
+pub fn solve(input: &[u8]) -> Result<(u32, u32), Error> {
+    let mut part1 = 0;
+    for (offset, l) in parse::lines(input) {
+        if l.len() % 2 == 1 {
+            return Err(Error::new(offset, l.len(), ErrorKind::OddLength));
+        }
+        let (first, second) = l.split_at(l.len() / 2);
+        let both = items(first, offset)? & items(second, offset + first.len())?;
+        part1 += only_item(both, offset, l.len())?;
+    }
+
+    let count = parse::lines(input).count();
+    if !count.is_multiple_of(3) {
+        let kind = ErrorKind::Lines {
+            count,
+            multiple_of: 3,
+        };
+        return Err(Error::new(0, 0, kind));
+    }
+    let mut part2 = 0;
+    let mut lines = parse::lines(input);
+    while let Some((offset, first)) = lines.next() {
+        let mut common = items(first, offset)?;
+        for (offset, l) in lines.by_ref().take(2) {
+            common &= items(l, offset)?;
+        }
+        part2 += only_item(common, offset, first.len())?;
+    }
+    Ok((part1, part2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let example = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+        assert_eq!(solve(example.as_bytes()), Ok((157, 70)));
+
+        for (bad, want) in [
+            ("abc", ErrorKind::OddLength),
+            ("ab", ErrorKind::NoCommonItem),
+            (
+                "aAbA\nxx",
+                ErrorKind::Lines {
+                    count: 2,
+                    multiple_of: 3,
+                },
+            ),
+            ("a1a1", ErrorKind::Expected("a letter")),
+            ("aa\nbb\ncc", ErrorKind::NoCommonItem),
+        ] {
+            assert_eq!(solve(bad.as_bytes()).unwrap_err().kind, want, "{bad:?}");
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use core::ops::RangeInclusive;
+
+use crate::{parse, Error, ErrorKind};
+
+fn range(s: &[u8], offset: usize) -> Result<RangeInclusive<u32>, Error> {
+    let (lower, upper) = parse::split_once(s, offset, b'-', "a range like \"2-4\"")?;
+    let lower = parse::uint(lower, offset)?;
+    let upper = parse::uint(upper, offset + s.len() - upper.len())?;
+    if lower > upper {
+        return Err(Error::new(offset, s.len(), ErrorKind::Reversed));
+    }
+    Ok(lower..=upper)
+}
+
+// Returns (pairs where one range contains the other, pairs that overlap at all)
+pub fn solve(input: &[u8]) -> Result<(u32, u32), Error> {
+    let mut part1 = 0;
+    let mut part2 = 0;
+    for (offset, l) in parse::lines(input) {
+        let (first, second) = parse::split_once(l, offset, b',', "a pair like \"2-4,6-8\"")?;
+        let a = range(first, offset)?;
+        let b = range(second, offset + first.len() + 1)?;
+        let contains = |x: &RangeInclusive<u32>, y: &RangeInclusive<u32>| {
+            x.contains(y.start()) && x.contains(y.end())
+        };
+        if contains(&a, &b) || contains(&b, &a) {
+            part1 += 1
+        }
+        if a.start() <= b.end() && b.start() <= a.end() {
+            part2 += 1
+        }
+    }
+    Ok((part1, part2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let example = "2-4,6-8\n2-3,4-5\n5-7,7-9\n2-8,3-7\n6-6,4-6\n2-6,4-8";
+        assert_eq!(solve(example.as_bytes()), Ok((2, 4)));
+
+        let err = solve(b"1-2,3-4\n1-2,3-x").unwrap_err();
+        assert_eq!((err.offset, err.kind), (14, ErrorKind::Expected("a digit")));
+        assert_eq!(solve(b"1-2,3-4,5-6").unwrap_err().offset, 7);
+        assert_eq!(solve(b"1-2-3,4-5").unwrap_err().offset, 3);
+        assert_eq!(solve(b"1-2").unwrap_err().len, 3);
+        let err = solve(b"1-2,4-3").unwrap_err();
+        assert_eq!((err.offset, err.len, err.kind), (4, 3, ErrorKind::Reversed));
+    }
+}
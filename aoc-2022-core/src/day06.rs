@@ -0,0 +1,60 @@
+use crate::{parse, Error, ErrorKind};
+
+// Number of characters read when the last `size` were all different, looking only at the first
+// line. Keeps a count per letter for the window, so each character is looked at twice at most.
+pub fn find_marker(input: &[u8], size: usize) -> Result<usize, Error> {
+    let line = parse::lines(input).next().map_or(&[][..], |(_, l)| l);
+    let mut counts = [0_usize; 26];
+    let mut repeated = 0;
+    for (i, &b) in line.iter().enumerate() {
+        if !b.is_ascii_lowercase() {
+            return Err(Error::new(i, 1, ErrorKind::Expected("a lowercase letter")));
+        }
+        let c = &mut counts[(b - b'a') as usize];
+        *c += 1;
+        if *c == 2 {
+            repeated += 1
+        }
+        if i >= size {
+            let c = &mut counts[(line[i - size] - b'a') as usize];
+            *c -= 1;
+            if *c == 1 {
+                repeated -= 1
+            }
+        }
+        if i + 1 >= size && repeated == 0 {
+            return Ok(i + 1);
+        }
+    }
+    Err(Error::new(0, line.len(), ErrorKind::NoMarker))
+}
+
+// Returns (end of the start-of-packet marker, end of the start-of-message marker)
+pub fn solve(input: &[u8]) -> Result<(usize, usize), Error> {
+    Ok((find_marker(input, 4)?, find_marker(input, 14)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        for (input, want) in [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", (7, 19)),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", (5, 23)),
+            ("nppdvjthqldpwncqszvftbrmjlhg", (6, 23)),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", (10, 29)),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw\n", (11, 26)),
+        ] {
+            assert_eq!(solve(input.as_bytes()), Ok(want), "{input:?}")
+        }
+        assert_eq!(find_marker(b"abcd", 4), Ok(4));
+        assert_eq!(
+            find_marker(b"abca", 4).unwrap_err().kind,
+            ErrorKind::NoMarker
+        );
+        assert_eq!(find_marker(b"", 4).unwrap_err().kind, ErrorKind::NoMarker);
+        assert_eq!(find_marker(b"abC", 4).unwrap_err().offset, 2);
+    }
+}
@@ -0,0 +1,59 @@
+use core::fmt;
+
+// What went wrong and where: `len` bytes from `offset` in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    pub offset: usize,
+    pub len: usize,
+    pub kind: ErrorKind,
+}
+
+impl Error {
+    pub fn new(offset: usize, len: usize, kind: ErrorKind) -> Self {
+        Self { offset, len, kind }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Expected(&'static str),
+    Overflow,
+    OddLength,
+    NoCommonItem,
+    Lines {
+        count: usize,
+        multiple_of: usize,
+    },
+    TooFew {
+        what: &'static str,
+        need: usize,
+        found: usize,
+    },
+    NoMarker,
+    Reversed,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expected(what) => write!(f, "expected {what}"),
+            Self::Overflow => write!(f, "number too large"),
+            Self::OddLength => write!(f, "line length must be even"),
+            Self::NoCommonItem => write!(f, "no single item in common"),
+            Self::Lines { count, multiple_of } => {
+                write!(f, "{count} lines, must be a multiple of {multiple_of}")
+            }
+            Self::TooFew { what, need, found } => {
+                write!(f, "need at least {need} {what}, found {found}")
+            }
+            Self::NoMarker => write!(f, "no marker found"),
+            Self::Reversed => write!(f, "range ends before it starts"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at byte {}: {}", self.offset, self.kind)
+    }
+}
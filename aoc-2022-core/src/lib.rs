@@ -0,0 +1,13 @@
+// Solvers for the simplest days that don't need std or allocate at all. Inputs are bytes and
+// errors carry the byte offset of the culprit, so the std side (aoc-2022) can point at it.
+#![cfg_attr(not(test), no_std)]
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day06;
+mod error;
+mod parse;
+
+pub use error::{Error, ErrorKind};
@@ -0,0 +1,112 @@
+use crate::{Error, ErrorKind};
+
+// Like str::lines: splits on LF, drops a trailing CR, and there's no empty line after a final LF.
+// Yields each line with its offset in the input.
+pub(crate) fn lines(input: &[u8]) -> Lines<'_> {
+    Lines { input, pos: 0 }
+}
+
+pub(crate) struct Lines<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = (usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.input.len() {
+            return None;
+        }
+        let start = self.pos;
+        let rest = &self.input[start..];
+        let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.pos = start + end + 1;
+        let line = &rest[..end];
+        Some((start, line.strip_suffix(b"\r").unwrap_or(line)))
+    }
+}
+
+pub(crate) fn uint(s: &[u8], offset: usize) -> Result<u32, Error> {
+    if s.is_empty() {
+        return Err(Error::new(offset, 0, ErrorKind::Expected("a number")));
+    }
+    let mut n: u32 = 0;
+    for (i, &b) in s.iter().enumerate() {
+        if !b.is_ascii_digit() {
+            return Err(Error::new(offset + i, 1, ErrorKind::Expected("a digit")));
+        }
+        n = n
+            .checked_mul(10)
+            .and_then(|n| n.checked_add((b - b'0') as u32))
+            .ok_or(Error::new(offset, s.len(), ErrorKind::Overflow))?;
+    }
+    Ok(n)
+}
+
+// Splits at the only `sep`, erroring if there isn't exactly one.
+pub(crate) fn split_once<'a>(
+    s: &'a [u8],
+    offset: usize,
+    sep: u8,
+    expected: &'static str,
+) -> Result<(&'a [u8], &'a [u8]), Error> {
+    let mut found = s
+        .iter()
+        .enumerate()
+        .filter(|(_, &b)| b == sep)
+        .map(|(i, _)| i);
+    match (found.next(), found.next()) {
+        (Some(i), None) => Ok((&s[..i], &s[i + 1..])),
+        (Some(_), Some(j)) => Err(Error::new(offset + j, 1, ErrorKind::Expected(expected))),
+        (None, _) => Err(Error::new(offset, s.len(), ErrorKind::Expected(expected))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        for (input, want) in [
+            ("", vec![]),
+            ("a", vec![(0, "a")]),
+            ("a\n", vec![(0, "a")]),
+            ("a\r\n\r\nbc", vec![(0, "a"), (3, ""), (5, "bc")]),
+            ("\n", vec![(0, "")]),
+        ] {
+            let got: Vec<_> = lines(input.as_bytes())
+                .map(|(o, l)| (o, std::str::from_utf8(l).unwrap()))
+                .collect();
+            assert_eq!(got, want, "{input:?}")
+        }
+    }
+
+    #[test]
+    fn test_uint() {
+        assert_eq!(uint(b"4294967295", 0), Ok(u32::MAX));
+        assert_eq!(
+            uint(b"4294967296", 3),
+            Err(Error::new(3, 10, ErrorKind::Overflow))
+        );
+        assert_eq!(
+            uint(b"12x", 3),
+            Err(Error::new(5, 1, ErrorKind::Expected("a digit")))
+        );
+        assert_eq!(
+            uint(b"", 3),
+            Err(Error::new(3, 0, ErrorKind::Expected("a number")))
+        );
+    }
+
+    #[test]
+    fn test_split_once() {
+        assert_eq!(
+            split_once(b"1-2", 0, b'-', "'-'"),
+            Ok((&b"1"[..], &b"2"[..]))
+        );
+        assert_eq!(split_once(b"1-2-3", 5, b'-', "'-'").unwrap_err().offset, 8);
+        assert_eq!(split_once(b"12", 5, b'-', "'-'").unwrap_err().len, 2);
+    }
+}
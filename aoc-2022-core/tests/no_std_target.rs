@@ -0,0 +1,41 @@
+use std::{path::Path, process::Command};
+
+// Targets without std. Building for one proves nothing (including dependencies) pulls std in.
+const TARGETS: [&str; 4] = [
+    "thumbv7em-none-eabihf",
+    "thumbv6m-none-eabi",
+    "riscv32imac-unknown-none-elf",
+    "x86_64-unknown-none",
+];
+
+// Needs one of TARGETS installed, so it only runs when asked for:
+//
+//   rustup target add thumbv7em-none-eabihf
+//   cargo test -p aoc-2022-core --test no_std_target -- --ignored
+#[test]
+#[ignore = "needs a no_std target, see the comment above"]
+fn builds_for_no_std_target() {
+    let out = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .expect("running rustc");
+    let sysroot = String::from_utf8(out.stdout).unwrap();
+    let rustlib = Path::new(sysroot.trim()).join("lib/rustlib");
+    let Some(target) = TARGETS.iter().find(|t| rustlib.join(t).exists()) else {
+        panic!(
+            "no no_std target installed, try `rustup target add {}`",
+            TARGETS[0]
+        );
+    };
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--target", target, "--manifest-path"])
+        .arg(Path::new(manifest_dir).join("Cargo.toml"))
+        // A separate directory so this doesn't wait on the lock held by the outer `cargo test`
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std"))
+        .status()
+        .expect("running cargo");
+    assert!(status.success(), "building for {target} failed");
+}
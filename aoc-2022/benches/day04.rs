@@ -0,0 +1,38 @@
+use aoc_2022::rng::Lcg;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// The real thing, rather than a copy that could drift from it. Its main and tests go unused.
+#[allow(dead_code, unused_imports)]
+#[path = "../src/bin/day04.rs"]
+mod day04;
+
+use day04::{duplicates, pairs, sweep};
+
+// `n` lines of random pairs of ranges, up to a thousand sections each within a million
+fn generate(n: usize) -> String {
+    let mut rng = Lcg::new(4);
+    let mut range = || {
+        let lower = rng.below(1_000_000);
+        format!("{lower}-{}", lower + rng.below(1000))
+    };
+    (0..n)
+        .map(|_| format!("{},{}\n", range(), range()))
+        .collect()
+}
+
+fn bench_sweep(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sweep");
+    group.sample_size(10);
+    for n in [10_000, 100_000, 1_000_000] {
+        let pairs = pairs(&generate(n)).unwrap();
+        group.bench_with_input(BenchmarkId::new("coverage", n), &pairs, |b, p| {
+            b.iter(|| sweep(black_box(p)))
+        });
+        group.bench_with_input(BenchmarkId::new("duplicates", n), &pairs, |b, p| {
+            b.iter(|| duplicates(black_box(p), 9))
+        });
+    }
+}
+
+criterion_group!(benches, bench_sweep);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+use aoc_2022::{drawing, rng::Lcg};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// The real solvers, parsing included: day05's stacks are ropes, day05_nom's still Vecs. Their
+// mains and tests go unused.
+#[allow(dead_code, unused_imports)]
+#[path = "../src/bin/day05.rs"]
+mod day05;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/bin/day05_nom.rs"]
+mod day05_nom;
+
+// `crates` lettered crates spread over 9 stacks, then `moves` moves that each take a random share
+// of their source stack, so big stacks mean big moves
+fn generate(crates: usize, moves: usize) -> String {
+    let mut rng = Lcg::new(5);
+    let mut below = |n| rng.below(n);
+    let mut stacks = vec![Vec::new(); 9];
+    for _ in 0..crates {
+        let letter = char::from(b'A' + below(26) as u8);
+        stacks[below(9)].push(letter.to_string());
+    }
+    let mut heights: Vec<_> = stacks.iter().map(Vec::len).collect();
+    let mut input = drawing::crates(&stacks).join("\n");
+    input += "\n\n";
+    let mut added = 0;
+    while added < moves {
+        let (src, dest) = (below(9), below(9));
+        if src == dest || heights[src] == 0 {
+            continue;
+        }
+        let count = below(heights[src]) + 1;
+        heights[src] -= count;
+        heights[dest] += count;
+        input += &format!("move {count} from {} to {}\n", src + 1, dest + 1);
+        added += 1;
+    }
+    input
+}
+
+fn bench_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crate_moves");
+    group.sample_size(10);
+    for crates in [10_000, 100_000, 1_000_000] {
+        let input = generate(crates, 1000);
+        group.bench_with_input(BenchmarkId::new("rope slow", crates), &input, |b, i| {
+            b.iter(|| day05::part1(black_box(i)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("rope fast", crates), &input, |b, i| {
+            b.iter(|| day05::part2(black_box(i)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("vec slow", crates), &input, |b, i| {
+            b.iter(|| day05_nom::part1(black_box(i)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("vec fast", crates), &input, |b, i| {
+            b.iter(|| day05_nom::part2(black_box(i)).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, bench_moves);
+criterion_main!(benches);
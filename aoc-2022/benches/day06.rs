@@ -1,6 +1,3 @@
-// Need nightly for const generic windows()
-#![feature(array_windows)]
-
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use std::collections::{BTreeSet, HashSet};
 
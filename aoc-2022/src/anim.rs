@@ -0,0 +1,278 @@
+use std::{fs, path::Path};
+#[cfg(feature = "anim")]
+use std::{
+    io::{stdout, Write},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+#[cfg(feature = "anim")]
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+
+use crate::cli;
+
+// One step of a simulation, drawn as lines of text.
+pub trait Frame {
+    fn draw(&self) -> String;
+}
+
+// Plays (`--animate`) or dumps (`--frames <dir>`) the frames from `make`, if either was asked for.
+// Playing needs the `anim` feature for terminal control; dumping works without it.
+pub fn from_args<F: Frame>(make: impl FnOnce() -> Result<Vec<F>>) -> Result<()> {
+    let animate = cli::has_flag("--animate");
+    let dir = cli::arg_value("--frames");
+    if !animate && dir.is_none() {
+        return Ok(());
+    }
+    let frames = make()?;
+    if let Some(dir) = dir {
+        dump(&frames, Path::new(&dir))?;
+    }
+    if animate {
+        Player::default().play(&frames)?;
+    }
+    Ok(())
+}
+
+// Writes frame N to `dir/frame_N.txt`, zero padded so they sort.
+pub fn dump<F: Frame>(frames: &[F], dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("creating {dir:?}"))?;
+    let digits = frames.len().max(1).ilog10() as usize + 1;
+    for (i, f) in frames.iter().enumerate() {
+        let path = dir.join(format!("frame_{i:0digits$}.txt"));
+        fs::write(&path, f.draw()).with_context(|| format!("writing {path:?}"))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    TogglePause,
+    Forward,
+    Back,
+    Faster,
+    Slower,
+    Quit,
+}
+
+#[cfg(feature = "anim")]
+impl Key {
+    fn from_event(e: KeyEvent) -> Option<Self> {
+        if e.kind == KeyEventKind::Release {
+            return None;
+        }
+        let k = match e.code {
+            KeyCode::Char(' ') => Self::TogglePause,
+            KeyCode::Right | KeyCode::Char('.') => Self::Forward,
+            KeyCode::Left | KeyCode::Char(',') => Self::Back,
+            KeyCode::Char('+' | '=') => Self::Faster,
+            KeyCode::Char('-') => Self::Slower,
+            KeyCode::Char('q') | KeyCode::Esc => Self::Quit,
+            _ => return None,
+        };
+        Some(k)
+    }
+}
+
+// Playback position and controls, kept apart from the terminal so it can be tested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayState {
+    pub index: usize,
+    pub len: usize,
+    pub playing: bool,
+    pub fps: f64,
+}
+
+impl PlayState {
+    pub fn new(len: usize, fps: f64) -> Self {
+        Self {
+            index: 0,
+            len,
+            playing: true,
+            fps,
+        }
+    }
+
+    // Returns false once playback should stop.
+    pub fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::TogglePause => self.playing = !self.playing,
+            Key::Forward => {
+                self.playing = false;
+                self.step()
+            }
+            Key::Back => {
+                self.playing = false;
+                self.index = self.index.saturating_sub(1)
+            }
+            Key::Faster => self.fps = (self.fps * 2.).min(1000.),
+            Key::Slower => self.fps = (self.fps / 2.).max(0.5),
+            Key::Quit => return false,
+        }
+        true
+    }
+
+    // Advances while playing, pausing on the last frame.
+    pub fn tick(&mut self) {
+        if self.playing {
+            self.step();
+            if self.index + 1 >= self.len {
+                self.playing = false
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        self.index = (self.index + 1).min(self.len.saturating_sub(1))
+    }
+
+    #[cfg_attr(not(feature = "anim"), allow(dead_code))]
+    fn status(&self) -> String {
+        format!(
+            "frame {}/{}  {:.1} fps  {}  [space] play/pause  [<-/->] step  [+/-] speed  [q] quit",
+            self.index + 1,
+            self.len,
+            self.fps,
+            if self.playing { "playing" } else { "paused " },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub fps: f64,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self { fps: 10. }
+    }
+}
+
+// Restores the terminal even if playback fails part way.
+#[cfg(feature = "anim")]
+struct RawTerminal;
+
+#[cfg(feature = "anim")]
+impl RawTerminal {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+#[cfg(feature = "anim")]
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Player {
+    #[cfg(not(feature = "anim"))]
+    pub fn play<F: Frame>(&self, _: &[F]) -> Result<()> {
+        anyhow::bail!("--animate needs the `anim` feature, or write the frames out with --frames")
+    }
+
+    #[cfg(feature = "anim")]
+    pub fn play<F: Frame>(&self, frames: &[F]) -> Result<()> {
+        if frames.is_empty() {
+            return Ok(());
+        }
+        let _term = RawTerminal::enter()?;
+        let mut out = stdout();
+        let mut state = PlayState::new(frames.len(), self.fps);
+        loop {
+            queue!(
+                out,
+                cursor::MoveTo(0, 0),
+                terminal::Clear(ClearType::All),
+                // Raw mode doesn't return the cursor to the start of the line
+                Print(frames[state.index].draw().replace('\n', "\r\n")),
+                Print("\r\n\r\n"),
+                Print(state.status()),
+            )?;
+            out.flush()?;
+
+            let wait = if state.playing {
+                Duration::from_secs_f64(1. / state.fps)
+            } else {
+                Duration::from_secs(3600)
+            };
+            if event::poll(wait)? {
+                if let Event::Key(e) = event::read()? {
+                    if let Some(k) = Key::from_event(e) {
+                        if !state.handle(k) {
+                            return Ok(());
+                        }
+                    }
+                }
+            } else {
+                state.tick()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Num(usize);
+
+    impl Frame for Num {
+        fn draw(&self) -> String {
+            format!("{}\n", self.0)
+        }
+    }
+
+    #[test]
+    fn test_play_state() {
+        let mut s = PlayState::new(3, 10.);
+        s.tick();
+        assert_eq!((s.index, s.playing), (1, true));
+        s.tick();
+        assert_eq!((s.index, s.playing), (2, false));
+        s.tick();
+        assert_eq!(s.index, 2);
+
+        assert!(s.handle(Key::Back));
+        assert!(s.handle(Key::Back));
+        assert!(s.handle(Key::Back));
+        assert_eq!((s.index, s.playing), (0, false));
+        assert!(s.handle(Key::Forward));
+        assert_eq!(s.index, 1);
+        assert!(s.handle(Key::TogglePause));
+        assert!(s.playing);
+
+        assert!(s.handle(Key::Faster));
+        assert_eq!(s.fps, 20.);
+        assert!(s.handle(Key::Slower));
+        assert!(s.handle(Key::Slower));
+        assert_eq!(s.fps, 5.);
+
+        assert!(!s.handle(Key::Quit));
+    }
+
+    #[test]
+    fn test_dump() {
+        let dir = std::env::temp_dir().join(format!("aoc-anim-test-{}", std::process::id()));
+        let frames: Vec<_> = (0..12).map(Num).collect();
+        dump(&frames, &dir).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("frame_00.txt")).unwrap(), "0\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("frame_11.txt")).unwrap(),
+            "11\n"
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 12);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{self, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use aoc_2022::{
+    budget::{self, parse_duration},
+    lint, par,
+    trace::{self, Format},
+};
+
+// Runs the main binary for each day and prints what it printed, in day order. With the `parallel`
+// feature the days run at the same time. `--trace <file>` collects every day's spans into one
+// file, see src/trace.rs. `--budget <time>` (or AOC_BUDGET) is passed on to each day, whose long
+// parts check it, and also limits each day's process as a whole, see kill_after and src/budget.rs.
+// `aoc lint <day> <input>` checks an input against what that day assumes about it, see
+// src/lint.rs. The binaries need building first:
+//
+//   cargo build --release --bins && target/release/aoc [--trace <file>] [--budget 5s] [day...]
+
+const DAYS: [&str; 15] = [
+    "day01", "day02", "day03", "day04", "day05", "day06", "day07", "day08", "day09", "day10",
+    "day11", "day12", "day13", "day14", "day15",
+];
+
+// The days that declare assumptions, which handle `--lint` themselves. Linting any other day needs
+// nothing from it.
+const LINTED: [&str; 4] = ["day05", "day12", "day13", "day14"];
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    days: Vec<&'static str>,
+    trace: Option<PathBuf>,
+    budget: Option<Duration>,
+}
+
+fn main() -> Result<()> {
+    let argv: Vec<String> = env::args().skip(1).collect();
+    if argv.first().is_some_and(|a| a == "lint") {
+        return lint(&argv[1..]);
+    }
+    let mut args = parse_args(argv.into_iter())?;
+    if args.budget.is_none() {
+        args.budget = env::var(budget::ENV)
+            .ok()
+            .map(|b| parse_duration(&b))
+            .transpose()?;
+    }
+    let exe = env::current_exe()?;
+    let dir = exe.parent().context("no directory for the runner")?;
+    // Each day writes its own trace here, to be merged once they're all done
+    let trace_dir = env::temp_dir().join(format!("aoc-trace-{}", process::id()));
+    let trace_format = args.trace.as_deref().map(Format::from_path).transpose()?;
+    let day_trace =
+        |day: &str| trace_format.map(|f| trace_dir.join(format!("{day}.{}", f.extension())));
+    if trace_format.is_some() {
+        fs::create_dir_all(&trace_dir)?;
+    }
+
+    let start = Instant::now();
+    let runs = par::map(&args.days, |day| {
+        let trace_args = day_trace(day).map(|t| vec!["--trace".into(), t.into_os_string()]);
+        run(dir, day, &trace_args.unwrap_or_default(), args.budget)
+    });
+    let mut failed = Vec::new();
+    for (day, r) in args.days.iter().zip(runs) {
+        match r {
+            Ok(r) => {
+                match r.status {
+                    Some(_) => println!("== {day} ({:.2?}) ==", r.took),
+                    None => println!("== {day} TIMEOUT after {:.2?} ==", r.took),
+                }
+                print!("{}", String::from_utf8_lossy(&r.stdout));
+                eprint!("{}", String::from_utf8_lossy(&r.stderr));
+                if !r.status.is_some_and(|s| s.success()) {
+                    failed.push(*day)
+                }
+            }
+            Err(e) => {
+                println!("== {day} ==");
+                eprintln!("{e:#}");
+                failed.push(*day)
+            }
+        }
+    }
+    let mode = if par::ENABLED {
+        "in parallel"
+    } else {
+        "in turn"
+    };
+    eprintln!(
+        "ran {} days {mode} in {:.2?}",
+        args.days.len(),
+        start.elapsed()
+    );
+
+    if let Some(out) = &args.trace {
+        let parts: Vec<_> = args
+            .days
+            .iter()
+            .filter_map(|day| Some((*day, day_trace(day)?)))
+            .filter(|(_, p)| p.exists())
+            .collect();
+        trace::merge(&parts, out)?;
+        fs::remove_dir_all(&trace_dir)?;
+        eprintln!("wrote trace for {} days to {}", parts.len(), out.display());
+    }
+    ensure!(failed.is_empty(), "failed: {}", failed.join(", "));
+    Ok(())
+}
+
+struct Run {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    // None if it was killed for going over budget
+    status: Option<ExitStatus>,
+    took: Duration,
+}
+
+// Only days 11, 12 (both variants), 14 and 15 check the budget in their parts. For the rest it's
+// this, a limit on the whole process: a day's main can run two inputs of two parts each, plus a
+// little to start up. A day killed here is reported with the time the process ran, not per part.
+fn kill_after(budget: Duration) -> Duration {
+    budget * 4 + Duration::from_secs(1)
+}
+
+fn lint(args: &[String]) -> Result<()> {
+    let [day, input] = args else {
+        bail!("usage: aoc lint <day> <input>");
+    };
+    let day = select([day.clone()].into_iter())?[0];
+    if !LINTED.contains(&day) {
+        fs::metadata(input).with_context(|| format!("reading {input}"))?;
+        print!("{}", lint::report(input, &[]));
+        return Ok(());
+    }
+    let exe = env::current_exe()?;
+    let dir = exe.parent().context("no directory for the runner")?;
+    let r = run(dir, day, &["--lint".into(), input.into()], None)?;
+    print!("{}", String::from_utf8_lossy(&r.stdout));
+    eprint!("{}", String::from_utf8_lossy(&r.stderr));
+    // The day has already said what's wrong
+    process::exit(r.status.and_then(|s| s.code()).unwrap_or(1))
+}
+
+fn run(dir: &Path, day: &str, args: &[OsString], budget: Option<Duration>) -> Result<Run> {
+    let path = dir.join(format!("{day}{}", env::consts::EXE_SUFFIX));
+    ensure!(
+        path.exists(),
+        "{} not found, build it with `cargo build --bins`",
+        path.display()
+    );
+    let mut cmd = Command::new(&path);
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    cmd.args(args);
+    if let Some(b) = budget {
+        cmd.env(budget::ENV, b.as_secs_f64().to_string());
+    }
+
+    let start = Instant::now();
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("running {}", path.display()))?;
+    // Read output as it comes, so a chatty child can't block on a full pipe
+    let stdout = read_all(child.stdout.take());
+    let stderr = read_all(child.stderr.take());
+    let status = match budget.map(kill_after) {
+        None => Some(child.wait()?),
+        Some(limit) => loop {
+            if let Some(s) = child.try_wait()? {
+                break Some(s);
+            }
+            if start.elapsed() > limit {
+                child.kill()?;
+                child.wait()?;
+                break None;
+            }
+            thread::sleep(Duration::from_millis(5));
+        },
+    };
+    let took = start.elapsed();
+    Ok(Run {
+        stdout: stdout.join().expect("reading stdout")?,
+        stderr: stderr.join().expect("reading stderr")?,
+        status,
+        took,
+    })
+}
+
+fn read_all(r: Option<impl Read + Send + 'static>) -> thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut r) = r {
+            r.read_to_end(&mut buf)?;
+        }
+        Ok(buf)
+    })
+}
+
+// Splits out `--trace <file>` and `--budget <time>` (or `--name=value`) from the days.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let mut parsed = Args::default();
+    let mut days = Vec::new();
+    while let Some(a) = args.next() {
+        let (name, value) = match a.split_once('=') {
+            Some((n, v)) if n.starts_with("--") => (n.to_owned(), Some(v.to_owned())),
+            _ => (a, None),
+        };
+        if !name.starts_with("--") {
+            days.push(name);
+            continue;
+        }
+        let value = match value {
+            Some(v) => v,
+            None => args
+                .next()
+                .with_context(|| format!("{name} needs a value"))?,
+        };
+        match name.as_str() {
+            "--trace" => parsed.trace = Some(value.into()),
+            "--budget" => parsed.budget = Some(parse_duration(&value)?),
+            _ => bail!("unknown option {name}, expected --trace or --budget"),
+        }
+    }
+    parsed.days = select(days.into_iter())?;
+    Ok(parsed)
+}
+
+// Days named by the arguments ("7", "07" or "day07"), or all of them if there are none.
+fn select(args: impl Iterator<Item = String>) -> Result<Vec<&'static str>> {
+    let mut days = Vec::new();
+    for a in args {
+        let n = a.strip_prefix("day").unwrap_or(&a);
+        let Some(day) = n
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| DAYS.get(n.wrapping_sub(1)))
+        else {
+            bail!("no day {a:?}, expected 1 to {}", DAYS.len());
+        };
+        if !days.contains(day) {
+            days.push(*day)
+        }
+    }
+    if days.is_empty() {
+        days = DAYS.to_vec();
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(a: &[&str]) -> impl Iterator<Item = String> {
+        a.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(select(args(&[])).unwrap(), DAYS);
+        assert_eq!(
+            select(args(&["12", "day03", "3", "07"])).unwrap(),
+            ["day12", "day03", "day07"]
+        );
+        for bad in ["0", "16", "day", "x", "-1"] {
+            assert!(select(args(&[bad])).is_err(), "{bad}");
+        }
+    }
+
+    // LINTED is every day whose main handles --lint, and no others
+    #[test]
+    fn test_linted() {
+        for day in DAYS {
+            let src = fs::read_to_string(format!("src/bin/{day}.rs")).unwrap();
+            assert_eq!(src.contains("lint_arg("), LINTED.contains(&day), "{day}");
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(args(&["3", "--trace", "t.json", "4", "--budget=1.5"])).unwrap(),
+            Args {
+                days: vec!["day03", "day04"],
+                trace: Some("t.json".into()),
+                budget: Some(Duration::from_millis(1500)),
+            }
+        );
+        assert_eq!(
+            parse_args(args(&["--trace=t.folded"])).unwrap(),
+            Args {
+                days: DAYS.to_vec(),
+                trace: Some("t.folded".into()),
+                budget: None,
+            }
+        );
+        for bad in [
+            &["--trace"][..],
+            &["--tracing", "x"],
+            &["--budget", "soon"],
+            &["--budget"],
+        ] {
+            assert!(parse_args(args(bad)).is_err(), "{bad:?}");
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use anyhow::Result;
+use anyhow::{ensure, Result};
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{checked, diag::parse_span, solver, stats::TopK};
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -7,28 +10,33 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day01.txt");
     println!("{:#?}", solve(d)?);
     Ok(())
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let mut acc: u32 = 0;
-    let mut elves = vec![];
+    let mut elves = TopK::new(3);
     for l in input.lines() {
         if !l.is_empty() {
-            acc += l.parse::<u32>()?;
+            acc = checked::add(solver!(), acc, parse_span::<u32>(input, l)?)?;
             continue;
         }
         elves.push(acc);
         acc = 0;
     }
     elves.push(acc);
-    elves.sort();
-    elves.reverse();
-    let top3: u32 = elves[..3].iter().sum();
-    let max = elves.into_iter().max().unwrap_or(0);
-    Ok(Answer { max, top3 })
+    let top: Vec<_> = elves
+        .into_sorted_vec()
+        .into_iter()
+        .map(|(_, c)| c)
+        .collect();
+    ensure!(top.len() == 3, "Need at least 3 elves, found {}", top.len());
+    let top3 = checked::sum(solver!(), 0, top.iter().copied())?;
+    Ok(Answer { max: top[0], top3 })
 }
 
 #[cfg(test)]
@@ -58,4 +66,26 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_too_few_elves() {
+        for input in ["", "1", "1\n2\n\n3"] {
+            let err = solve(input).unwrap_err();
+            assert!(
+                err.to_string().starts_with("Need at least 3 elves"),
+                "{err}"
+            );
+        }
+        let answer = solve("1\n\n2\n\n3").unwrap();
+        assert_eq!(answer, Answer { max: 3, top3: 6 });
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let err = solve("4294967295\n1\n\n1\n\n1").unwrap_err();
+        assert_eq!(err.to_string(), "day01: overflow in 4294967295 + 1 (u32)");
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day01.txt"), solve);
 }
@@ -0,0 +1,239 @@
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    ops::RangeInclusive,
+};
+
+use anyhow::{Context, Result};
+use aoc_2022::cli::arg_value;
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{
+    checked,
+    diag::{name_input, parse_span},
+    solver,
+    stats::{Stats, TopK},
+};
+use tracing::instrument;
+
+// The same as day01, but reading from any BufRead while holding only the current line and the
+// best `n` elves, so inputs far bigger than memory work. Try `--input -` with a generated input
+// on stdin, and `--top 10`.
+
+// Line numbers count from 1, like diagnostics
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Elf {
+    index: usize,
+    lines: RangeInclusive<usize>,
+    calories: u64,
+}
+
+// Most calories first, then the earlier elf. Indices are unique, so this agrees with Eq.
+impl Ord for Elf {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.calories
+            .cmp(&other.calories)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Elf {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn main() -> Result<()> {
+    let _trace = trace_arg()?;
+    let n = match arg_value("--top") {
+        Some(n) => n.parse().with_context(|| format!("--top {n:?}"))?,
+        None => 3,
+    };
+    // Parse errors are reported against the input they came from
+    let (name, top) = match arg_value("--input").as_deref() {
+        None => (
+            "challenge/day01.txt".to_owned(),
+            top_elves(include_str!("../../data/challenge/day01.txt").as_bytes(), n),
+        ),
+        Some("-") => ("stdin".to_owned(), top_elves(io::stdin().lock(), n)),
+        Some(path) => (
+            path.to_owned(),
+            top_elves(
+                BufReader::new(File::open(path).context(path.to_owned())?),
+                n,
+            ),
+        ),
+    };
+    let (top, all) = top.map_err(|e| name_input(e, &name))?;
+    if let (Some(min), Some(max), Some(mean)) = (all.min, all.max, all.mean()) {
+        println!(
+            "{} elves, carrying {min} to {max}, {mean:.1} on average",
+            all.count
+        );
+    }
+    for (i, elf) in top.iter().enumerate() {
+        println!(
+            "#{}: elf {} (lines {}-{}) carries {}",
+            i + 1,
+            elf.index,
+            elf.lines.start(),
+            elf.lines.end(),
+            elf.calories
+        );
+    }
+    let total = checked::sum(solver!(), 0, top.iter().map(|e| e.calories))?;
+    println!("top {} total: {total}", top.len());
+    Ok(())
+}
+
+// The `n` elves carrying the most, most first, and a summary of what every elf carries. Any run
+// of blank lines separates two elves, and lines may end in CRLF.
+#[instrument(skip_all)]
+fn top_elves(mut input: impl BufRead, n: usize) -> Result<(Vec<Elf>, Stats<u128>)> {
+    let mut top = TopK::new(n);
+    // Wider than the calories, so the sum over every elf can't overflow
+    let mut all = Stats::default();
+    let mut finish = |elf: Option<Elf>| {
+        if let Some(elf) = elf {
+            all.push(elf.calories as u128);
+            top.push(elf);
+        }
+    };
+    let mut line = String::new();
+    let mut line_num = 0;
+    let mut elves = 0;
+    let mut current: Option<Elf> = None;
+    loop {
+        line.clear();
+        if input.read_line(&mut line).context("reading")? == 0 {
+            break;
+        }
+        line_num += 1;
+        let l = line.trim_end_matches(['\n', '\r']);
+        if l.is_empty() {
+            finish(current.take());
+            continue;
+        }
+        let calories: u64 = parse_span(l, l).map_err(|d| d.on_line(line_num))?;
+        let elf = current.get_or_insert_with(|| {
+            elves += 1;
+            Elf {
+                index: elves - 1,
+                lines: line_num..=line_num,
+                calories: 0,
+            }
+        });
+        elf.lines = *elf.lines.start()..=line_num;
+        elf.calories = checked::add(solver!(), elf.calories, calories)?;
+    }
+    finish(current);
+    let top = top.into_sorted_vec().into_iter().map(|(_, e)| e).collect();
+    Ok((top, all))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn calories(elves: &[Elf]) -> Vec<u64> {
+        elves.iter().map(|e| e.calories).collect()
+    }
+
+    #[test]
+    fn example() {
+        let (top, all) =
+            top_elves(include_str!("../../data/example/day01.txt").as_bytes(), 3).unwrap();
+        assert_eq!(calories(&top), [24000, 11000, 10000]);
+        assert_eq!((all.count, all.min, all.max), (5, Some(4000), Some(24000)));
+        assert_eq!(all.mean(), Some(11000.0));
+        assert_eq!(
+            top[0],
+            Elf {
+                index: 3,
+                lines: 10..=12,
+                calories: 24000
+            }
+        );
+    }
+
+    #[test]
+    fn challenge() {
+        let (top, _) =
+            top_elves(include_str!("../../data/challenge/day01.txt").as_bytes(), 3).unwrap();
+        assert_eq!(top[0].calories, 68787);
+        assert_eq!(top.iter().map(|e| e.calories).sum::<u64>(), 198041);
+    }
+
+    #[test]
+    fn test_separators() {
+        // CRLF, leading, trailing and repeated blank lines
+        let (top, _) = top_elves("\r\n1\r\n2\r\n\r\n\r\n\r\n5\n\n\n".as_bytes(), 5).unwrap();
+        let lines: Vec<_> = top.iter().map(|e| e.lines.clone()).collect();
+        assert_eq!(calories(&top), [5, 3]);
+        assert_eq!(lines, [7..=7, 2..=3]);
+        assert_eq!(top[0].index, 1);
+        assert!(top_elves("\n\n".as_bytes(), 3).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_ties() {
+        let (top, _) = top_elves("4\n\n9\n\n4\n\n4".as_bytes(), 3).unwrap();
+        let indices: Vec<_> = top.iter().map(|e| e.index).collect();
+        assert_eq!(indices, [1, 0, 2]);
+    }
+
+    #[test]
+    fn test_error_line() {
+        let err = top_elves("1\n\n2\n2x\n".as_bytes(), 3).unwrap_err();
+        let err = name_input(err, "elves.txt");
+        let d = err.downcast_ref::<aoc_2022::diag::Diagnostic>().unwrap();
+        assert_eq!((d.line, d.column), (4, 1));
+        assert!(err.to_string().starts_with("elves.txt:4:1: "), "{err}");
+    }
+
+    // Elf `i` carries `i % 1000` and then 1, one number per line, made up as it's read
+    struct Generated {
+        elves: usize,
+        next: usize,
+        buf: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Read for Generated {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            if self.pos == self.buf.len() {
+                if self.next == self.elves {
+                    return Ok(0);
+                }
+                self.buf = format!("{}\n1\n\n", self.next % 1000).into_bytes();
+                self.pos = 0;
+                self.next += 1;
+            }
+            let n = out.len().min(self.buf.len() - self.pos);
+            out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_generated() {
+        let input = Generated {
+            elves: 200_000,
+            next: 0,
+            buf: Vec::new(),
+            pos: 0,
+        };
+        let (top, _) = top_elves(BufReader::new(input), 4).unwrap();
+        assert_eq!(calories(&top), [1000; 4]);
+        let indices: Vec<_> = top.iter().map(|e| e.index).collect();
+        assert_eq!(indices, [999, 1999, 2999, 3999]);
+        assert_eq!(top[0].lines, 2998..=2999);
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day01.txt"), |s| {
+        top_elves(s.as_bytes(), 3)
+    });
+}
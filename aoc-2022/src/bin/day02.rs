@@ -1,4 +1,11 @@
-use anyhow::{anyhow, Result};
+use std::cmp::Reverse;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use aoc_2022::cli::{arg_value, has_flag};
+use aoc_2022::diag::Diagnostic;
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{checked, solver};
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -7,18 +14,26 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day02.txt");
-    println!("{:#?}", solve(d)?);
+    let rules = if has_flag("--rpsls") {
+        // The input only has A to C and X to Z, so some shapes never come up
+        Rules::rock_paper_scissors_lizard_spock()
+    } else {
+        Rules::rock_paper_scissors()
+    };
+    let only = match arg_value("--total") {
+        Some(t) => Some(t.parse().with_context(|| format!("--total {t:?}"))?),
+        None => None,
+    };
+    if has_flag("--explore") || only.is_some() {
+        explore(&rules, d, only)?;
+    } else {
+        println!("{:#?}", solve_with(&rules, d)?);
+    }
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Choice {
-    Rock,
-    Paper,
-    Scissors,
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Outcome {
     Lose,
@@ -26,96 +41,310 @@ enum Outcome {
     Win,
 }
 
-impl Choice {
-    fn from_opponent(c: char) -> Result<Self> {
-        match c {
-            'A' => Ok(Choice::Rock),
-            'B' => Ok(Choice::Paper),
-            'C' => Ok(Choice::Scissors),
-            _ => Err(anyhow!("Unrecognized {}", c)),
+impl Outcome {
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Lose => "lose",
+            Outcome::Draw => "draw",
+            Outcome::Win => "win",
         }
     }
+}
+
+// What column 2 means: a shape to play (part 1), or how far round from theirs to play (part 2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reading {
+    Shapes,
+    Outcomes,
+}
 
-    fn from_self(c: char) -> Result<Self> {
-        match c {
-            'X' => Ok(Choice::Rock),
-            'Y' => Ok(Choice::Paper),
-            'Z' => Ok(Choice::Scissors),
-            _ => Err(anyhow!("Unrecognized {}", c)),
+// One way of reading column 2, where the nth `mine` symbol means `to[n]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Interpretation {
+    reading: Reading,
+    to: Vec<usize>,
+    total: u32,
+}
+
+// How often each `counts[theirs][symbol]` comes up, which is all any reading needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Guide {
+    counts: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Shape {
+    name: &'static str,
+    // Column 1, and column 2 when it means a shape (part 1)
+    theirs: char,
+    mine: char,
+    score: u32,
+}
+
+// A game where shapes sit on a cycle and whether one beats another only depends on how far
+// round the cycle it is. Shapes are referred to by their index in `shapes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rules {
+    shapes: Vec<Shape>,
+    // Shape `i` beats shape `i - d` for each `d` here
+    beats: Vec<usize>,
+    // When column 2 means an outcome (part 2), the nth `mine` symbol says to play the shape
+    // this far round from theirs
+    plan: Vec<usize>,
+    // Indexed by Outcome
+    outcome_scores: [u32; 3],
+}
+
+impl Rules {
+    fn new(shapes: Vec<Shape>, beats: Vec<usize>, plan: Vec<usize>) -> Result<Self> {
+        let n = shapes.len();
+        ensure!(n > 0, "A game needs shapes");
+        // Exactly one of each pair beats the other, which rules out an even number of shapes
+        for d in 1..n {
+            ensure!(
+                beats.contains(&d) != beats.contains(&(n - d)),
+                "Shapes {d} apart must have exactly one winner"
+            );
         }
+        ensure!(
+            beats.iter().all(|&d| 0 < d && d < n),
+            "Offsets in beats must be between 1 and {}",
+            n - 1
+        );
+        ensure!(plan.len() == n, "Need a plan for each of {n} symbols");
+        ensure!(
+            plan.iter().all(|&d| d < n),
+            "Plans must be less than {n} round"
+        );
+        for col in [|s: &Shape| s.theirs, |s: &Shape| s.mine] {
+            let mut symbols: Vec<_> = shapes.iter().map(col).collect();
+            symbols.sort();
+            symbols.dedup();
+            ensure!(symbols.len() == n, "Symbols must be distinct");
+        }
+        Ok(Self {
+            shapes,
+            beats,
+            plan,
+            outcome_scores: [0, 3, 6],
+        })
     }
 
-    fn for_outcome(&self, outcome: Outcome) -> Self {
-        if Outcome::Draw == outcome {
-            return *self;
-        }
-        match (self, outcome) {
-            (Choice::Rock, Outcome::Lose) => Choice::Scissors,
-            (Choice::Rock, Outcome::Win) => Choice::Paper,
-            (Choice::Paper, Outcome::Lose) => Choice::Rock,
-            (Choice::Paper, Outcome::Win) => Choice::Scissors,
-            (Choice::Scissors, Outcome::Lose) => Choice::Paper,
-            (Choice::Scissors, Outcome::Win) => Choice::Rock,
-            _ => unreachable!(),
+    // Each shape beats the half of the others just before it
+    fn cyclic(shapes: Vec<Shape>, plan: Vec<usize>) -> Result<Self> {
+        let beats = (1..=shapes.len() / 2).collect();
+        Self::new(shapes, beats, plan)
+    }
+
+    fn rock_paper_scissors() -> Self {
+        let shapes = vec![
+            shape("Rock", 'A', 'X', 1),
+            shape("Paper", 'B', 'Y', 2),
+            shape("Scissors", 'C', 'Z', 3),
+        ];
+        // X, Y and Z mean lose, draw and win
+        Self::cyclic(shapes, vec![2, 0, 1]).expect("valid rules")
+    }
+
+    // In this order each shape beats the two before it
+    fn rock_paper_scissors_lizard_spock() -> Self {
+        let shapes = vec![
+            shape("Rock", 'A', 'V', 1),
+            shape("Spock", 'B', 'W', 2),
+            shape("Paper", 'C', 'X', 3),
+            shape("Lizard", 'D', 'Y', 4),
+            shape("Scissors", 'E', 'Z', 5),
+        ];
+        // V to Z go from the worst loss to the best win
+        Self::cyclic(shapes, vec![3, 4, 0, 1, 2]).expect("valid rules")
+    }
+
+    fn find(&self, c: char, col: fn(&Shape) -> char) -> Result<usize> {
+        self.shapes
+            .iter()
+            .position(|s| col(s) == c)
+            .ok_or_else(|| anyhow!("Unrecognized {}", c))
+    }
+
+    fn theirs(&self, c: char) -> Result<usize> {
+        self.find(c, |s| s.theirs)
+    }
+
+    fn mine(&self, c: char) -> Result<usize> {
+        self.find(c, |s| s.mine)
+    }
+
+    fn play(&self, reading: Reading, to: &[usize], theirs: usize, symbol: usize) -> usize {
+        match reading {
+            Reading::Shapes => to[symbol],
+            Reading::Outcomes => (theirs + to[symbol]) % self.shapes.len(),
         }
     }
 
-    fn shape_score(&self) -> u32 {
-        match self {
-            Choice::Rock => 1,
-            Choice::Paper => 2,
-            Choice::Scissors => 3,
+    fn outcome(&self, mine: usize, theirs: usize) -> Outcome {
+        let n = self.shapes.len();
+        let d = (mine + n - theirs) % n;
+        if d == 0 {
+            Outcome::Draw
+        } else if self.beats.contains(&d) {
+            Outcome::Win
+        } else {
+            Outcome::Lose
         }
     }
 
-    fn match_score(&self, other: &Choice) -> u32 {
-        if self == other {
-            return 3;
+    fn score(&self, mine: usize, theirs: usize) -> u32 {
+        self.shapes[mine].score + self.outcome_scores[self.outcome(mine, theirs) as usize]
+    }
+
+    fn total(&self, guide: &Guide, reading: Reading, to: &[usize]) -> Result<u32> {
+        let mut total: u32 = 0;
+        for (theirs, row) in guide.counts.iter().enumerate() {
+            for (symbol, &count) in row.iter().enumerate() {
+                let score = self.score(self.play(reading, to, theirs, symbol), theirs);
+                total = checked::add(solver!(), total, checked::mul(solver!(), count, score)?)?;
+            }
         }
-        match (self, other) {
-            (Choice::Rock, Choice::Paper) => 0,
-            (Choice::Rock, Choice::Scissors) => 6,
-            (Choice::Paper, Choice::Rock) => 6,
-            (Choice::Paper, Choice::Scissors) => 0,
-            (Choice::Scissors, Choice::Rock) => 0,
-            (Choice::Scissors, Choice::Paper) => 6,
-            _ => unreachable!(),
+        Ok(total)
+    }
+
+    // Both readings of column 2 with every assignment of meanings to symbols, n! of each
+    fn interpretations(&self, guide: &Guide) -> Result<Vec<Interpretation>> {
+        let mut all = Vec::new();
+        for reading in [Reading::Shapes, Reading::Outcomes] {
+            for to in permutations(self.shapes.len()) {
+                let total = self.total(guide, reading, &to)?;
+                all.push(Interpretation { reading, to, total });
+            }
         }
+        Ok(all)
+    }
+
+    // e.g. "X=Rock Y=Paper Z=Scissors" or "X=lose Y=draw Z=win"
+    fn describe(&self, i: &Interpretation) -> String {
+        let meanings = i.to.iter().map(|&to| match i.reading {
+            Reading::Shapes => self.shapes[to].name.to_owned(),
+            Reading::Outcomes => {
+                let outcome = self.outcome(to, 0);
+                // Bigger games win and lose in more than one way
+                if outcome == Outcome::Draw || self.beats.len() == 1 {
+                    outcome.name().to_owned()
+                } else {
+                    format!("{}+{to}", outcome.name())
+                }
+            }
+        });
+        self.shapes
+            .iter()
+            .zip(meanings)
+            .map(|(s, m)| format!("{}={m}", s.mine))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 }
 
-impl Outcome {
-    fn from_char(c: char) -> Result<Self> {
-        match c {
-            'X' => Ok(Outcome::Lose),
-            'Y' => Ok(Outcome::Draw),
-            'Z' => Ok(Outcome::Win),
-            _ => Err(anyhow!("Unrecognized {}", c)),
+fn shape(name: &'static str, theirs: char, mine: char, score: u32) -> Shape {
+    Shape {
+        name,
+        theirs,
+        mine,
+        score,
+    }
+}
+
+// Every ordering of 0..n, in lexicographic order so the identity comes first. There are n! of
+// them, so keep n small.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    let mut p: Vec<usize> = (0..n).collect();
+    let mut all = vec![p.clone()];
+    // Step to the next ordering: bump the last position that can grow, then reset the rest
+    while let Some(i) = (1..n).rev().find(|&i| p[i - 1] < p[i]).map(|i| i - 1) {
+        let j = (i + 1..n).rev().find(|&j| p[j] > p[i]).unwrap_or(i + 1);
+        p.swap(i, j);
+        p[i + 1..].reverse();
+        all.push(p.clone());
+    }
+    all
+}
+
+#[instrument(skip_all)]
+fn solve_with(rules: &Rules, input: &str) -> Result<Answer> {
+    let guide = guide(rules, input)?;
+    let identity: Vec<_> = (0..rules.shapes.len()).collect();
+    // Part 1 reads column 2 as a shape, part 2 as the nth outcome
+    let part1 = rules.total(&guide, Reading::Shapes, &identity)?;
+    let part2 = rules.total(&guide, Reading::Outcomes, &rules.plan)?;
+    Ok(Answer { part1, part2 })
+}
+
+fn guide(rules: &Rules, input: &str) -> Result<Guide> {
+    let n = rules.shapes.len();
+    let mut counts = vec![vec![0; n]; n];
+    for l in input.lines() {
+        let chars: Vec<_> = l.char_indices().collect();
+        if chars.len() < 3 {
+            let msg = format!("line length is {}, expected 3", chars.len());
+            return Err(Diagnostic::new(input, l, msg).into());
         }
+        // Points errors at the offending column
+        let at = |i: usize| {
+            let (pos, c) = chars[i];
+            let span = &l[pos..pos + c.len_utf8()];
+            move |e: anyhow::Error| Diagnostic::new(input, span, e)
+        };
+        let theirs = rules.theirs(chars[0].1).map_err(at(0))?;
+        let symbol = rules.mine(chars[2].1).map_err(at(2))?;
+        counts[theirs][symbol] += 1;
     }
+    Ok(Guide { counts })
 }
 
-fn solve(input: &str) -> Result<Answer> {
-    let mut part1 = 0;
-    let mut part2 = 0;
-    for (i, l) in input.lines().enumerate() {
-        let l: Vec<_> = l.chars().collect();
-        if l.len() < 3 {
-            return Err(anyhow!("line {} length is {}, expected 3", i, l.len()));
+// Totals for every reading of the guide, best first, or just those scoring `only`
+fn explore(rules: &Rules, input: &str, only: Option<u32>) -> Result<()> {
+    let mut all = rules.interpretations(&guide(rules, input)?)?;
+    // Stable, so equal totals stay in permutation order
+    all.sort_by_key(|i| Reverse(i.total));
+    for i in all.iter().filter(|i| only.is_none_or(|t| i.total == t)) {
+        println!("{:>8}  {:?}: {}", i.total, i.reading, rules.describe(i));
+    }
+    if let (Some(best), Some(worst)) = (all.first(), all.last()) {
+        for (what, i) in [("best", best), ("worst", worst)] {
+            println!(
+                "{what}: {:?}: {} ({})",
+                i.reading,
+                rules.describe(i),
+                i.total
+            );
         }
-        let other = Choice::from_opponent(l[0])?;
-        let mine_part1 = Choice::from_self(l[2])?;
-        let mine_part2 = other.for_outcome(Outcome::from_char(l[2])?);
-        part1 += mine_part1.shape_score() + mine_part1.match_score(&other);
-        part2 += mine_part2.shape_score() + mine_part2.match_score(&other);
     }
-    Ok(Answer { part1, part2 })
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn solve(input: &str) -> Result<Answer> {
+        solve_with(&Rules::rock_paper_scissors(), input)
+    }
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(
+            permutations(3),
+            [
+                [0, 1, 2],
+                [0, 2, 1],
+                [1, 0, 2],
+                [1, 2, 0],
+                [2, 0, 1],
+                [2, 1, 0]
+            ]
+        );
+        assert_eq!(permutations(5).len(), 120);
+        assert_eq!(permutations(0), [Vec::<usize>::new()]);
+    }
+
     #[test]
     fn example() {
         let answer = solve(include_str!("../../data/example/day02.txt")).unwrap();
@@ -139,4 +368,115 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_error_location() {
+        for (input, want) in [
+            ("A Y\nB Q", (2, 3)),
+            ("A Y\nD X", (2, 1)),
+            ("A Y\nB", (2, 1)),
+        ] {
+            let err = solve(input).unwrap_err();
+            let d = err.downcast_ref::<Diagnostic>().unwrap();
+            assert_eq!((d.line, d.column), want, "{d}");
+        }
+    }
+
+    #[test]
+    fn test_rpsls() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        for (winner, loser) in [
+            ("Scissors", "Paper"),
+            ("Paper", "Rock"),
+            ("Rock", "Lizard"),
+            ("Lizard", "Spock"),
+            ("Spock", "Scissors"),
+            ("Scissors", "Lizard"),
+            ("Lizard", "Paper"),
+            ("Paper", "Spock"),
+            ("Spock", "Rock"),
+            ("Rock", "Scissors"),
+        ] {
+            let find = |name| rules.shapes.iter().position(|s| s.name == name).unwrap();
+            let (w, l) = (find(winner), find(loser));
+            assert_eq!(rules.outcome(w, l), Outcome::Win, "{winner} vs {loser}");
+            assert_eq!(rules.outcome(l, w), Outcome::Lose, "{loser} vs {winner}");
+        }
+        // Rock draws with Rock but V is the worst loss, to Lizard. Scissors beats Paper either way.
+        let answer = solve_with(&rules, "A V\nC Z").unwrap();
+        assert_eq!(
+            answer,
+            Answer {
+                part1: 15,
+                part2: 15
+            }
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let shapes = |n: u32| -> Vec<Shape> {
+            (0..n)
+                .map(|i| {
+                    let c = |base| char::from_u32(base as u32 + i).unwrap();
+                    shape("s", c('A'), c('a'), i + 1)
+                })
+                .collect()
+        };
+        assert!(Rules::cyclic(shapes(7), vec![0; 7]).is_ok());
+        assert!(Rules::cyclic(shapes(4), vec![0; 4]).is_err());
+        assert!(Rules::new(shapes(3), vec![1, 2], vec![0; 3]).is_err());
+        assert!(Rules::new(shapes(5), vec![1, 3], vec![0; 5]).is_ok());
+        assert!(Rules::cyclic(shapes(3), vec![0; 2]).is_err());
+        let mut same = shapes(3);
+        same[1].mine = 'a';
+        assert!(Rules::cyclic(same, vec![0; 3]).is_err());
+    }
+
+    #[test]
+    fn test_interpretations() {
+        let rules = Rules::rock_paper_scissors();
+        let guide = guide(&rules, include_str!("../../data/example/day02.txt")).unwrap();
+        let all = rules.interpretations(&guide).unwrap();
+        assert_eq!(all.len(), 12);
+        let find = |reading, to: &[usize]| {
+            all.iter()
+                .find(|i| i.reading == reading && i.to == to)
+                .unwrap()
+        };
+        // The two readings the puzzle uses
+        assert_eq!(find(Reading::Shapes, &[0, 1, 2]).total, 15);
+        let plan = find(Reading::Outcomes, &[2, 0, 1]);
+        assert_eq!(plan.total, 12);
+        assert_eq!(rules.describe(plan), "X=lose Y=draw Z=win");
+        // A Y, B X, C Z: Y=Rock draws, X=Scissors wins, Z=Paper loses
+        let i = find(Reading::Shapes, &[2, 0, 1]);
+        assert_eq!(i.total, 4 + 9 + 2);
+        assert_eq!(rules.describe(i), "X=Scissors Y=Rock Z=Paper");
+
+        let best = all.iter().map(|i| i.total).max().unwrap();
+        let worst = all.iter().map(|i| i.total).min().unwrap();
+        assert_eq!((best, worst), (24, 6));
+        // The example is symmetric enough that 8 of the 12 give 15, but only one gives 18
+        assert_eq!(all.iter().filter(|i| i.total == 15).count(), 8);
+        let matching: Vec<_> = all.iter().filter(|i| i.total == 18).collect();
+        assert_eq!(matching.len(), 1);
+        assert_eq!(rules.describe(matching[0]), "X=win Y=lose Z=draw");
+    }
+
+    #[test]
+    fn test_describe_bigger_game() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        let i = Interpretation {
+            reading: Reading::Outcomes,
+            to: rules.plan.clone(),
+            total: 0,
+        };
+        assert_eq!(
+            rules.describe(&i),
+            "V=lose+3 W=lose+4 X=draw Y=win+1 Z=win+2"
+        );
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day02.txt"), solve);
 }
@@ -1,6 +1,10 @@
-use std::collections::HashSet;
+use std::fmt;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Result};
+use aoc_2022::diag::Diagnostic;
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{checked, solver};
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -8,6 +12,7 @@ struct Answer {
     part2: u32,
 }
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day03.txt");
     println!("{:#?}", solve(d)?);
     Ok(())
@@ -37,57 +42,130 @@ impl Prioritized for char {
     }
 }
 
-fn part1(input: &str) -> Result<u32> {
-    let mut total = 0;
-    for (i, l) in input.lines().enumerate() {
-        let l: Vec<_> = l.chars().collect();
-        if l.len() % 2 == 1 {
-            return Err(anyhow!("line {} had length {}, must be even", i, l.len()));
-        }
-        let mid = l.len() / 2;
-        let first: HashSet<_> = l[..mid].iter().collect();
-        let second: HashSet<_> = l[mid..].iter().collect();
-        let inter: Vec<_> = first.intersection(&second).collect();
-        if inter.len() != 1 {
-            return Err(anyhow!(
-                "intersection {:?} had length {}, must be 1",
-                inter,
-                inter.len()
-            ));
-        }
-        total += inter[0].priority()?
+// Items as bits, bit `p` for priority `p`, so sets of them are single words
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+struct ItemSet(u64);
+
+impl ItemSet {
+    // Priorities 1 to 52
+    const ALL: Self = Self(((1 << 52) - 1) << 1);
+
+    fn single(item: impl Prioritized) -> Result<Self> {
+        Ok(Self(1 << item.priority()?))
     }
 
-    Ok(total)
+    fn from_items(s: &str) -> Result<Self> {
+        s.chars()
+            .try_fold(Self::default(), |set, c| Ok(set.union(Self::single(c)?)))
+    }
+
+    fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    fn len(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Lowest first
+    fn priorities(self) -> impl Iterator<Item = u32> {
+        (1..=52).filter(move |p| self.0 & (1 << p) != 0)
+    }
+
+    fn items(self) -> impl Iterator<Item = char> {
+        self.priorities().map(|p| match p {
+            1..=26 => (b'a' + p as u8 - 1) as char,
+            _ => (b'A' + p as u8 - 27) as char,
+        })
+    }
 }
 
-fn part2(input: &str) -> Result<u32> {
-    let lines: Vec<_> = input.lines().collect();
-    if lines.len() % 3 != 0 {
-        return Err(anyhow!("{} lines, must be multiple of 3", lines.len()));
+impl fmt::Debug for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.items()).finish()
     }
-    let mut total = 0;
-    for group in lines.chunks_exact(3) {
-        let mut all: Vec<HashSet<_>> = group.iter().map(|l| l.chars().collect()).collect();
+}
 
-        let mut inter = all.pop().unwrap();
-        for b in all {
-            inter.retain(|e| b.contains(e));
+// The priority of the one item in every part, where `parts` are sub-slices of `input`. Errors
+// point at the part that left nothing in common, or at the first part if too much is left.
+fn only_common(input: &str, parts: &[&str], what: &str) -> Result<u32> {
+    let mut common = ItemSet::ALL;
+    for part in parts {
+        let items = ItemSet::from_items(part).map_err(|e| Diagnostic::new(input, part, e))?;
+        common = common.intersection(items);
+        if common.is_empty() {
+            let msg = format!("nothing in common with the rest of {what}");
+            return Err(Diagnostic::new(input, part, msg).into());
+        }
+    }
+    match (common.len(), common.priorities().next()) {
+        (1, Some(p)) => Ok(p),
+        (n, _) => {
+            let msg = format!("{what} share {n} items, {common:?}, must be exactly 1");
+            let first = parts.first().copied().unwrap_or(input);
+            Err(Diagnostic::new(input, first, msg).into())
         }
+    }
+}
 
-        if inter.len() != 1 {
-            return Err(anyhow!(
-                "intersection {:?} had length {}, must be 1",
-                inter,
-                inter.len()
-            ));
+// Sum over lines of the item common to all `m` equal compartments of the line
+fn compartments(input: &str, m: usize) -> Result<u32> {
+    ensure!(m > 0, "need at least one compartment");
+    let what = format!("the line's {m} compartments");
+    let mut total = 0;
+    for line in input.lines() {
+        let ends: Vec<_> = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([line.len()])
+            .collect();
+        let len = ends.len() - 1;
+        if len % m != 0 {
+            let msg = format!("line had length {len}, must be a multiple of {m}");
+            return Err(Diagnostic::new(input, line, msg).into());
         }
-        total += inter.iter().next().unwrap().priority()?
+        let parts: Vec<_> = (0..m)
+            .map(|i| &line[ends[i * len / m]..ends[(i + 1) * len / m]])
+            .collect();
+        total = checked::add(solver!(), total, only_common(input, &parts, &what)?)?;
     }
+    Ok(total)
+}
 
+// Sum over groups of `k` lines of the item common to every line in the group
+fn groups_of(input: &str, k: usize) -> Result<u32> {
+    ensure!(k > 0, "groups need at least one line");
+    let lines: Vec<_> = input.lines().collect();
+    if lines.len() % k != 0 {
+        return Err(anyhow!("{} lines, must be multiple of {k}", lines.len()));
+    }
+    let what = format!("the group of {k} lines");
+    let mut total = 0;
+    for group in lines.chunks_exact(k) {
+        total = checked::add(solver!(), total, only_common(input, group, &what)?)?;
+    }
     Ok(total)
 }
 
+#[instrument(skip_all)]
+fn part1(input: &str) -> Result<u32> {
+    compartments(input, 2)
+}
+
+#[instrument(skip_all)]
+fn part2(input: &str) -> Result<u32> {
+    groups_of(input, 3)
+}
+
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -122,4 +200,67 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_item_set() {
+        let a = ItemSet::from_items("abcA").unwrap();
+        let b = ItemSet::from_items("cZAa").unwrap();
+        assert_eq!(a.intersection(b).items().collect::<String>(), "acA");
+        assert_eq!(
+            a.union(b).priorities().collect::<Vec<_>>(),
+            [1, 2, 3, 27, 52]
+        );
+        assert_eq!(format!("{:?}", a.intersection(b)), "{'a', 'c', 'A'}");
+        assert_eq!(ItemSet::ALL.len(), 52);
+        assert!(ItemSet::from_items("").unwrap().is_empty());
+        assert!(ItemSet::from_items("ab1").is_err());
+    }
+
+    #[test]
+    fn test_sizes() {
+        // Each line on its own, so it must only repeat one item
+        assert_eq!(groups_of("aaa\nBB", 1).unwrap(), 1 + 28);
+        assert_eq!(compartments("aXbaYcaZd", 3).unwrap(), 1);
+        assert_eq!(compartments("zz\nqq", 1).unwrap(), 26 + 17);
+        assert!(compartments("abcab", 3).is_err());
+        assert!(groups_of("a\na\na", 2).is_err());
+        assert!(groups_of("a", 0).is_err());
+    }
+
+    #[test]
+    fn test_error_lines() {
+        let check = |err: anyhow::Error, want: (usize, usize, &str)| {
+            let d = err.downcast_ref::<Diagnostic>().unwrap();
+            assert_eq!((d.line, d.column, d.message.as_str()), want);
+        };
+        let err = groups_of("ab\nac\nxy", 3).unwrap_err();
+        check(
+            err,
+            (
+                3,
+                1,
+                "nothing in common with the rest of the group of 3 lines",
+            ),
+        );
+        let err = groups_of("xab\nab\nbac", 3).unwrap_err();
+        check(
+            err,
+            (
+                1,
+                1,
+                "the group of 3 lines share 2 items, {'a', 'b'}, must be exactly 1",
+            ),
+        );
+        let err = compartments("aa\nabcd", 2).unwrap_err();
+        check(
+            err,
+            (
+                2,
+                3,
+                "nothing in common with the rest of the line's 2 compartments",
+            ),
+        );
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day03.txt"), solve);
 }
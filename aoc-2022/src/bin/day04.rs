@@ -1,6 +1,10 @@
-use std::str::FromStr;
+use std::{cmp::Ordering, collections::BTreeSet, str::FromStr};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::Result;
+use aoc_2022::cli::has_flag;
+use aoc_2022::diag::{parse_span, Diagnostic};
+use aoc_2022::trace::trace_arg;
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -9,92 +13,377 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day04.txt");
     println!("{:#?}", solve(d)?);
+    if has_flag("--sweep") {
+        print_sweep(&pairs(d)?);
+    }
+    if has_flag("--relations") {
+        let hist = histogram(&relations(d)?);
+        let most = hist.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+        for (r, n) in hist {
+            let bar = "#".repeat((n * 50).div_ceil(most) as usize);
+            println!("{:>12} {n:>5} {bar}", format!("{r:?}"));
+        }
+    }
     Ok(())
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct R {
     lower: u32,
     upper: u32,
 }
 
-impl R {
-    fn contains(&self, other: &R) -> bool {
-        self.lower <= other.lower && self.upper >= other.upper
+// How two ranges sit relative to each other (Allen's interval relations), reading the sections
+// `lower..=upper` as covering `lower..upper + 1`. So ranges meet when they're adjacent without
+// sharing a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    const ALL: [Relation; 13] = [
+        Relation::Before,
+        Relation::Meets,
+        Relation::Overlaps,
+        Relation::Starts,
+        Relation::During,
+        Relation::Finishes,
+        Relation::Equals,
+        Relation::FinishedBy,
+        Relation::Contains,
+        Relation::StartedBy,
+        Relation::OverlappedBy,
+        Relation::MetBy,
+        Relation::After,
+    ];
+
+    // One range contains the other (part 1)
+    const NESTED: [Relation; 7] = [
+        Relation::Starts,
+        Relation::During,
+        Relation::Finishes,
+        Relation::Equals,
+        Relation::FinishedBy,
+        Relation::Contains,
+        Relation::StartedBy,
+    ];
+
+    // The ranges share a section (part 2)
+    const SHARED: [Relation; 9] = [
+        Relation::Overlaps,
+        Relation::Starts,
+        Relation::During,
+        Relation::Finishes,
+        Relation::Equals,
+        Relation::FinishedBy,
+        Relation::Contains,
+        Relation::StartedBy,
+        Relation::OverlappedBy,
+    ];
+
+    // The relation of the second range to the first
+    fn inverse(self) -> Self {
+        use Relation::*;
+        match self {
+            Before => After,
+            Meets => MetBy,
+            Overlaps => OverlappedBy,
+            Starts => StartedBy,
+            During => Contains,
+            Finishes => FinishedBy,
+            Equals => Equals,
+            FinishedBy => Finishes,
+            Contains => During,
+            StartedBy => Starts,
+            OverlappedBy => Overlaps,
+            MetBy => Meets,
+            After => Before,
+        }
     }
+}
 
-    fn overlaps(&self, other: &R) -> bool {
-        let r = self.lower..=self.upper;
-        r.contains(&other.lower) || r.contains(&other.upper)
+impl R {
+    fn relation(&self, other: &R) -> Relation {
+        use Relation::*;
+        // Widened, as the end of a range is one past its last section
+        let (s1, e1) = (self.lower as u64, self.upper as u64 + 1);
+        let (s2, e2) = (other.lower as u64, other.upper as u64 + 1);
+        if e1 < s2 {
+            Before
+        } else if e1 == s2 {
+            Meets
+        } else if e2 <= s1 {
+            // After or MetBy
+            other.relation(self).inverse()
+        } else {
+            match (s1.cmp(&s2), e1.cmp(&e2)) {
+                (Ordering::Less, Ordering::Less) => Overlaps,
+                (Ordering::Less, Ordering::Equal) => FinishedBy,
+                (Ordering::Less, Ordering::Greater) => Contains,
+                (Ordering::Equal, Ordering::Less) => Starts,
+                (Ordering::Equal, Ordering::Equal) => Equals,
+                (Ordering::Equal, Ordering::Greater) => StartedBy,
+                (Ordering::Greater, Ordering::Less) => During,
+                (Ordering::Greater, Ordering::Equal) => Finishes,
+                (Ordering::Greater, Ordering::Greater) => OverlappedBy,
+            }
+        }
     }
 }
 
 impl FromStr for R {
-    type Err = anyhow::Error;
+    type Err = Diagnostic;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
         let parts = s.split('-').collect::<Vec<_>>();
         if parts.len() != 2 {
-            return Err(anyhow!(
-                "Range {:?} had {} parts, expected 2",
-                s,
-                parts.len()
-            ));
+            let msg = format!("Range {:?} had {} parts, expected 2", s, parts.len());
+            return Err(Diagnostic::new(s, s, msg));
+        }
+        let lower = parse_span(s, parts[0])?;
+        let upper = parse_span(s, parts[1])?;
+        if lower > upper {
+            return Err(Diagnostic::new(s, s, "Range ends before it starts"));
         }
-        let lower = parts[0].parse()?;
-        let upper = parts[1].parse()?;
         Ok(R { lower, upper })
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct P {
+pub(crate) struct P {
     first: R,
     second: R,
 }
 
 impl FromStr for P {
-    type Err = anyhow::Error;
+    type Err = Diagnostic;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
         let parts = s.split(',').collect::<Vec<_>>();
         if parts.len() != 2 {
-            return Err(anyhow!(
-                "Pair {:?} had {} parts, expected 2",
-                s,
-                parts.len()
-            ));
+            let msg = format!("Pair {:?} had {} parts, expected 2", s, parts.len());
+            return Err(Diagnostic::new(s, s, msg));
         }
-        let first = parts[0].parse()?;
-        let second = parts[1].parse()?;
+        let first = parts[0]
+            .parse()
+            .map_err(|d: Diagnostic| d.within(s, parts[0]))?;
+        let second = parts[1]
+            .parse()
+            .map_err(|d: Diagnostic| d.within(s, parts[1]))?;
         Ok(P { first, second })
     }
 }
 
-fn part1(input: &str) -> Result<u32> {
-    let mut subset = 0;
-    for (i, l) in input.lines().enumerate() {
-        let p: P = l.parse().with_context(|| format!("Line {i}"))?;
-        if p.first.contains(&p.second) || p.second.contains(&p.first) {
-            subset += 1
+// pub(crate) here and below for benches/day04.rs, which includes this file
+pub(crate) fn pairs(input: &str) -> Result<Vec<P>> {
+    let mut pairs = Vec::new();
+    for l in input.lines() {
+        pairs.push(l.parse().map_err(|d: Diagnostic| d.within(input, l))?);
+    }
+    Ok(pairs)
+}
+
+fn relations(input: &str) -> Result<Vec<Relation>> {
+    Ok(pairs(input)?
+        .iter()
+        .map(|p| p.first.relation(&p.second))
+        .collect())
+}
+
+fn count_in(relations: &[Relation], union: &[Relation]) -> u32 {
+    relations.iter().filter(|r| union.contains(r)).count() as u32
+}
+
+// How many pairs have each relation, in Relation::ALL order
+fn histogram(relations: &[Relation]) -> Vec<(Relation, u32)> {
+    Relation::ALL
+        .iter()
+        .map(|&r| (r, count_in(relations, &[r])))
+        .collect()
+}
+
+// What a sweep over every range in the input finds. Uncovered sections only count between the
+// lowest and highest assigned ones.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Sweep {
+    // The first stretch of sections with the most elves on them
+    most: (u32, R),
+    uncovered: Vec<R>,
+    covered_once: Vec<R>,
+}
+
+// Stretches of sections covered by the same number of ranges, in order. O(n log n) for n ranges.
+fn coverage(ranges: impl IntoIterator<Item = R>) -> Vec<(u32, R)> {
+    // +1 where a range starts, -1 just after it ends. Widened, as that can be past u32::MAX.
+    let mut events: Vec<(u64, i64)> = ranges
+        .into_iter()
+        .flat_map(|r| [(r.lower as u64, 1), (r.upper as u64 + 1, -1)])
+        .collect();
+    events.sort_unstable();
+    let mut runs: Vec<(u32, R)> = Vec::new();
+    let mut depth = 0_i64;
+    let mut i = 0;
+    while i < events.len() {
+        let at = events[i].0;
+        while i < events.len() && events[i].0 == at {
+            depth += events[i].1;
+            i += 1;
+        }
+        let Some(&(next, _)) = events.get(i) else {
+            break;
+        };
+        let run = R {
+            lower: at as u32,
+            upper: (next - 1) as u32,
+        };
+        match runs.last_mut() {
+            Some((d, last)) if *d == depth as u32 => last.upper = run.upper,
+            _ => runs.push((depth as u32, run)),
         }
     }
-    Ok(subset)
+    runs
 }
 
-fn part2(input: &str) -> Result<u32> {
-    let mut overlap = 0;
-    for l in input.lines() {
-        let p: P = l.parse()?;
-        if p.first.overlaps(&p.second) || p.second.overlaps(&p.first) {
-            overlap += 1
+pub(crate) fn sweep(pairs: &[P]) -> Option<Sweep> {
+    let runs = coverage(pairs.iter().flat_map(|p| [p.first, p.second]));
+    let with = |n| runs.iter().filter(move |(d, _)| *d == n).map(|(_, r)| *r);
+    let max = runs.iter().map(|(d, _)| *d).max()?;
+    Some(Sweep {
+        most: (max, with(max).next()?),
+        uncovered: with(0).collect(),
+        covered_once: with(1).collect(),
+    })
+}
+
+// The sections a pair covers between them, as one or two ranges, lowest first and not touching
+fn pieces(p: &P) -> Vec<R> {
+    let (a, b) = if p.first.lower <= p.second.lower {
+        (p.first, p.second)
+    } else {
+        (p.second, p.first)
+    };
+    if b.lower as u64 <= a.upper as u64 + 1 {
+        vec![R {
+            lower: a.lower,
+            upper: a.upper.max(b.upper),
+        }]
+    } else {
+        vec![a, b]
+    }
+}
+
+// For each pair, up to `cap` other pairs that cover every section it covers within one stretch of
+// theirs. A pair in two pieces covering another piece by piece isn't found, as that's four bounds
+// at once. Stretches are added in order of their lower end while the pairs sweep past it, so each
+// pair only has to look up upper ends that reach past its own: O(n log n + n * cap).
+pub(crate) fn duplicates(pairs: &[P], cap: usize) -> Vec<Vec<usize>> {
+    let pieces: Vec<_> = pairs.iter().map(pieces).collect();
+    let mut stretches: Vec<(R, usize)> = pieces
+        .iter()
+        .enumerate()
+        .flat_map(|(i, ps)| ps.iter().map(move |r| (*r, i)))
+        .collect();
+    stretches.sort_unstable_by_key(|(r, _)| r.lower);
+    let mut order: Vec<usize> = (0..pairs.len()).collect();
+    order.sort_unstable_by_key(|&i| pieces[i][0].lower);
+
+    // (upper, pair) for every stretch starting at or before the current pair
+    let mut open = BTreeSet::new();
+    let mut added = 0;
+    let mut dups = vec![Vec::new(); pairs.len()];
+    for i in order {
+        let (lower, upper) = (pieces[i][0].lower, pieces[i][pieces[i].len() - 1].upper);
+        while let Some(&(r, j)) = stretches.get(added) {
+            if r.lower > lower {
+                break;
+            }
+            open.insert((r.upper, j));
+            added += 1;
         }
+        let d = &mut dups[i];
+        d.extend(
+            open.range((upper, 0)..)
+                .map(|&(_, j)| j)
+                .filter(|&j| j != i)
+                .take(cap),
+        );
+        d.sort_unstable();
+    }
+    dups
+}
+
+fn print_sweep(pairs: &[P]) {
+    let show = |rs: &[R]| {
+        let total: u64 = rs.iter().map(|r| (r.upper - r.lower) as u64 + 1).sum();
+        let first: Vec<_> = rs
+            .iter()
+            .take(5)
+            .map(|r| format!("{}-{}", r.lower, r.upper))
+            .collect();
+        let more = if rs.len() > 5 { ", ..." } else { "" };
+        format!(
+            "{total} sections in {} stretches: {}{more}",
+            rs.len(),
+            first.join(", ")
+        )
+    };
+    let Some(sweep) = sweep(pairs) else {
+        println!("no assignments");
+        return;
+    };
+    let (max, at) = sweep.most;
+    println!(
+        "most elves on one section: {max} (first at {}-{})",
+        at.lower, at.upper
+    );
+    println!("covered by no one: {}", show(&sweep.uncovered));
+    println!("covered by one elf: {}", show(&sweep.covered_once));
+    // One more than shown, to know when there are more
+    let dups = duplicates(pairs, 9);
+    let duplicated: Vec<_> = dups
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| !d.is_empty())
+        .collect();
+    println!(
+        "pairs whose work another pair duplicates: {}",
+        duplicated.len()
+    );
+    for (i, d) in duplicated.iter().take(10) {
+        // Line numbers, like diagnostics
+        let lines: Vec<_> = d.iter().take(8).map(|j| (j + 1).to_string()).collect();
+        let more = if d.len() > 8 { ", ..." } else { "" };
+        println!("  line {}: lines {}{more}", i + 1, lines.join(", "));
     }
-    Ok(overlap)
 }
 
+#[instrument(skip_all)]
+fn part1(input: &str) -> Result<u32> {
+    Ok(count_in(&relations(input)?, &Relation::NESTED))
+}
+
+#[instrument(skip_all)]
+fn part2(input: &str) -> Result<u32> {
+    Ok(count_in(&relations(input)?, &Relation::SHARED))
+}
+
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -104,7 +393,10 @@ fn solve(input: &str) -> Result<Answer> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use super::*;
+    use aoc_2022::rng::Lcg;
 
     #[test]
     fn example() {
@@ -123,4 +415,158 @@ mod tests {
             }
         );
     }
+
+    // Allen's definitions for half-open intervals, written out directly
+    fn textbook(r: Relation, (s1, e1): (u32, u32), (s2, e2): (u32, u32)) -> bool {
+        match r {
+            Relation::Before => e1 < s2,
+            Relation::Meets => e1 == s2,
+            Relation::Overlaps => s1 < s2 && s2 < e1 && e1 < e2,
+            Relation::Starts => s1 == s2 && e1 < e2,
+            Relation::During => s2 < s1 && e1 < e2,
+            Relation::Finishes => s2 < s1 && e1 == e2,
+            Relation::Equals => s1 == s2 && e1 == e2,
+            _ => textbook(r.inverse(), (s2, e2), (s1, e1)),
+        }
+    }
+
+    #[test]
+    fn test_relations_exhaustive() {
+        let ranges: Vec<_> = (0..6)
+            .flat_map(|lower| (lower..6).map(move |upper| R { lower, upper }))
+            .collect();
+        let mut seen = HashSet::new();
+        for a in &ranges {
+            for b in &ranges {
+                let rel = a.relation(b);
+                seen.insert(rel);
+                let (ha, hb) = ((a.lower, a.upper + 1), (b.lower, b.upper + 1));
+                for r in Relation::ALL {
+                    assert_eq!(textbook(r, ha, hb), r == rel, "{a:?} {r:?} {b:?}");
+                }
+                assert_eq!(b.relation(a), rel.inverse(), "{a:?} {b:?}");
+
+                // The unions agree with looking at the sections themselves
+                let sa: HashSet<_> = (a.lower..=a.upper).collect();
+                let sb: HashSet<_> = (b.lower..=b.upper).collect();
+                let nested = sa.is_subset(&sb) || sb.is_subset(&sa);
+                assert_eq!(Relation::NESTED.contains(&rel), nested, "{a:?} {b:?}");
+                let shared = !sa.is_disjoint(&sb);
+                assert_eq!(Relation::SHARED.contains(&rel), shared, "{a:?} {b:?}");
+            }
+        }
+        assert_eq!(seen.len(), 13);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let hist = histogram(&relations(include_str!("../../data/example/day04.txt")).unwrap());
+        let nonzero: Vec<_> = hist.into_iter().filter(|(_, n)| *n > 0).collect();
+        assert_eq!(
+            nonzero,
+            [
+                (Relation::Before, 1),
+                (Relation::Meets, 1),
+                (Relation::Overlaps, 2),
+                (Relation::Finishes, 1),
+                (Relation::Contains, 1)
+            ]
+        );
+        assert!("5-4,1-2".parse::<P>().is_err());
+    }
+
+    fn r(lower: u32, upper: u32) -> R {
+        R { lower, upper }
+    }
+
+    #[test]
+    fn test_sweep() {
+        assert_eq!(sweep(&[]), None);
+        let runs = coverage([r(1, 3), r(2, 5), r(8, 9)]);
+        assert_eq!(
+            runs,
+            [
+                (1, r(1, 1)),
+                (2, r(2, 3)),
+                (1, r(4, 5)),
+                (0, r(6, 7)),
+                (1, r(8, 9))
+            ]
+        );
+        let sweep = sweep(&pairs("1-3,2-5\n8-9,8-8\n4294967294-4294967295,0-0").unwrap()).unwrap();
+        assert_eq!(sweep.most, (2, r(2, 3)));
+        assert_eq!(sweep.uncovered, [r(6, 7), r(10, 4294967293)]);
+        assert_eq!(
+            sweep.covered_once,
+            [r(0, 1), r(4, 5), r(9, 9), r(4294967294, 4294967295)]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let pairs = pairs("1-5,7-9\n2-3,8-8\n1-9,20-20\n1-3,4-6\n5-6,1-4").unwrap();
+        let want: [&[usize]; 5] = [&[2], &[2], &[], &[2, 4], &[2, 3]];
+        assert_eq!(duplicates(&pairs, 9), want);
+        assert!(duplicates(&pairs, 1).iter().all(|d| d.len() <= 1));
+    }
+
+    #[test]
+    fn test_duplicates_naive() {
+        let mut rng = Lcg::new(2022);
+        let mut below = |n| rng.below(n) as u32;
+        let mut random = || {
+            let lower = below(12);
+            r(lower, lower + below(4))
+        };
+        let pairs: Vec<_> = (0..300)
+            .map(|_| P {
+                first: random(),
+                second: random(),
+            })
+            .collect();
+        let sections = |p: &P| -> HashSet<u32> {
+            (p.first.lower..=p.first.upper)
+                .chain(p.second.lower..=p.second.upper)
+                .collect()
+        };
+        let dups = duplicates(&pairs, pairs.len());
+        for (i, p) in pairs.iter().enumerate() {
+            // Every section from the pair's lowest to its highest
+            let lower = p.first.lower.min(p.second.lower);
+            let upper = p.first.upper.max(p.second.upper);
+            let want: Vec<_> = (0..pairs.len())
+                .filter(|&j| j != i && (lower..=upper).all(|s| sections(&pairs[j]).contains(&s)))
+                .collect();
+            assert_eq!(dups[i], want, "{p:?}");
+        }
+    }
+
+    // Every pair covers 4 sections, starting one after the last, so none duplicates another. Then
+    // pairs that all share a first piece, so every pair duplicates every other. The million-pair
+    // version is benches/day04.rs.
+    #[test]
+    fn test_scale() {
+        let n = 2000;
+        let pairs: Vec<_> = (0..n)
+            .map(|i| P {
+                first: r(i, i + 2),
+                second: r(i + 1, i + 3),
+            })
+            .collect();
+        let sweep = sweep(&pairs).unwrap();
+        assert_eq!(sweep.most, (6, r(3, n - 1)));
+        assert!(sweep.uncovered.is_empty());
+        assert_eq!(sweep.covered_once, [r(0, 0), r(n + 2, n + 2)]);
+        assert!(duplicates(&pairs, 9).iter().all(|d| d.is_empty()));
+
+        let pairs: Vec<_> = (0..n)
+            .map(|i| P {
+                first: r(0, n),
+                second: r(i, i),
+            })
+            .collect();
+        assert!(duplicates(&pairs, 9).iter().all(|d| d.len() == 9));
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day04.txt"), solve);
 }
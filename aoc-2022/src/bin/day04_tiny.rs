@@ -1,11 +1,9 @@
-use std::str::FromStr;
+use anyhow::Result;
+use aoc_2022::diag::Diagnostic;
 
-use anyhow::{anyhow, Context, Result};
-use tinyvec::ArrayVec;
-
-// This is the same as day04, but uses ArrayVec to avoid heap allocations.
-// This still isn't no_std (due to formatting), but drastically reduces allocations.
-// On x86_64-pc-windows-msvc with Rust 1.65, it goes from ~6K to ~120
+// This is the same as day04, but solved by the no_std core crate (aoc-2022-core), which doesn't
+// allocate at all. Previously this used ArrayVec, taking allocations on x86_64-pc-windows-msvc
+// with Rust 1.65 from ~6K to ~120, but formatting errors still needed std. See tests/no_alloc.rs
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -19,90 +17,9 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct R {
-    lower: u32,
-    upper: u32,
-}
-
-impl R {
-    fn contains(&self, other: &R) -> bool {
-        self.lower <= other.lower && self.upper >= other.upper
-    }
-
-    fn overlaps(&self, other: &R) -> bool {
-        let r = self.lower..=self.upper;
-        r.contains(&other.lower) || r.contains(&other.upper)
-    }
-}
-
-impl FromStr for R {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        let parts: ArrayVec<[_; 2]> = s.split('-').collect();
-        if parts.len() != 2 {
-            return Err(anyhow!(
-                "Range {:?} had {} parts, expected 2",
-                s,
-                parts.len()
-            ));
-        }
-        let lower = parts[0].parse()?;
-        let upper = parts[1].parse()?;
-        Ok(R { lower, upper })
-    }
-}
-
-#[derive(Debug, PartialEq, Eq)]
-struct P {
-    first: R,
-    second: R,
-}
-
-impl FromStr for P {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        let parts: ArrayVec<[_; 2]> = s.split(',').collect();
-        if parts.len() != 2 {
-            return Err(anyhow!(
-                "Pair {:?} had {} parts, expected 2",
-                s,
-                parts.len()
-            ));
-        }
-        let first = parts[0].parse()?;
-        let second = parts[1].parse()?;
-        Ok(P { first, second })
-    }
-}
-
-fn part1(input: &str) -> Result<u32> {
-    let mut subset = 0;
-    for (i, l) in input.lines().enumerate() {
-        let p: P = l.parse().with_context(|| format!("Line {i}"))?;
-        if p.first.contains(&p.second) || p.second.contains(&p.first) {
-            subset += 1
-        }
-    }
-    Ok(subset)
-}
-
-fn part2(input: &str) -> Result<u32> {
-    let mut overlap = 0;
-    for l in input.lines() {
-        let p: P = l.parse()?;
-        if p.first.overlaps(&p.second) || p.second.overlaps(&p.first) {
-            overlap += 1
-        }
-    }
-    Ok(overlap)
-}
-
 fn solve(input: &str) -> Result<Answer> {
-    let part1 = part1(input)?;
-    let part2 = part2(input)?;
+    let (part1, part2) = aoc_2022_core::day04::solve(input.as_bytes())
+        .map_err(|e| Diagnostic::from_core(input, e))?;
 
     Ok(Answer { part1, part2 })
 }
@@ -128,4 +45,18 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_error_location() {
+        for (input, want) in [
+            ("1-2,3-4\n1-2,3-4,5-6", (2, 8)),
+            ("1-2,3-4\n1-2,4-3", (2, 5)),
+        ] {
+            let err = solve(input).unwrap_err();
+            let d = err.downcast_ref::<Diagnostic>().unwrap();
+            assert_eq!((d.line, d.column), want, "{d}");
+        }
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day04.txt"), solve);
 }
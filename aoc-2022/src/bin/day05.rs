@@ -1,16 +1,42 @@
-use std::str::FromStr;
+use std::{fmt, ops::Range, str::FromStr};
 
 use anyhow::{anyhow, Context, Result};
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{
+    anim::{self, Frame},
+    cli::optional_value,
+    diag::{parse_span, Diagnostic},
+    drawing,
+    lint::{lint_arg, Assumption},
+    rope::Rope,
+};
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
-    part1: String,
-    part2: String,
+    part1: Vec<String>,
+    part2: Vec<String>,
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
+    if lint_arg(&ASSUMPTIONS)? {
+        return Ok(());
+    }
     let d = include_str!("../../data/challenge/day05.txt");
     println!("{:#?}", solve(d)?);
+    if let Some(step) = optional_value("--board") {
+        let frames = frames(d, Crates::run_fast)?;
+        let step = match step {
+            Some(n) => n.parse().with_context(|| format!("--board {n:?}"))?,
+            None => frames.len() - 1,
+        };
+        let board = frames
+            .get(step)
+            .with_context(|| format!("only {} moves", frames.len() - 1))?;
+        println!("{board}");
+    }
+    anim::from_args(|| frames(d, Crates::run_fast))?;
     Ok(())
 }
 
@@ -21,40 +47,31 @@ struct Move {
 }
 
 impl FromStr for Move {
-    type Err = anyhow::Error;
+    type Err = Diagnostic;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
+        let missing = |what: &str| Diagnostic::at(s, s.len(), 1, format!("move has no {what}"));
         let mut parts = s.split(' ');
 
-        parts.next().ok_or_else(|| anyhow!("move has no parts"))?;
-        let count: usize = parts
-            .next()
-            .ok_or_else(|| anyhow!("move has no count"))?
-            .parse()
-            .context("move count")?;
-
-        parts
-            .next()
-            .ok_or_else(|| anyhow!("move has only 2 parts"))?;
-        let src: usize = parts
-            .next()
-            .ok_or_else(|| anyhow!("move has no source"))?
-            .parse()
-            .context("move count")?;
+        parts.next().ok_or_else(|| missing("parts"))?;
+        let count: usize = parse_span(s, parts.next().ok_or_else(|| missing("count"))?)?;
+
+        parts.next().ok_or_else(|| missing("\"from\""))?;
+        let src_part = parts.next().ok_or_else(|| missing("source"))?;
+        let src: usize = parse_span(s, src_part)?;
         if src < 1 {
-            return Err(anyhow!("source must be positive"));
+            return Err(Diagnostic::new(s, src_part, "source must be positive"));
         }
 
-        parts
-            .next()
-            .ok_or_else(|| anyhow!("move has only 4 parts"))?;
-        let dest: usize = parts
-            .next()
-            .ok_or_else(|| anyhow!("move has no destination"))?
-            .parse()
-            .context("move count")?;
+        parts.next().ok_or_else(|| missing("\"to\""))?;
+        let dest_part = parts.next().ok_or_else(|| missing("destination"))?;
+        let dest: usize = parse_span(s, dest_part)?;
         if dest < 1 {
-            return Err(anyhow!("source must be positive"));
+            return Err(Diagnostic::new(
+                s,
+                dest_part,
+                "destination must be positive",
+            ));
         }
 
         let (src, dest) = (src - 1, dest - 1);
@@ -63,9 +80,11 @@ impl FromStr for Move {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Stacks are ropes, so moving any number of crates is a split and a join, plus a reversal for
+// the CrateMover 9000, all O(log n)
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Crates {
-    columns: Vec<Vec<char>>,
+    columns: Vec<Rope<String>>,
 }
 
 impl Crates {
@@ -94,68 +113,257 @@ impl Crates {
         Ok(())
     }
 
+    // The top `count` crates of the source, in the order they were stacked
+    fn lift(&mut self, m: &Move) -> Rope<String> {
+        let src = &mut self.columns[m.src];
+        src.split_off(src.len() - m.count)
+    }
+
+    // One at a time, so the crates land upside down
     fn run_slow(&mut self, m: &Move) -> Result<()> {
-        self.columns[m.dest].reserve(m.count);
-        for _ in 0..m.count {
-            let tmp = self.columns[m.src].pop().unwrap();
-            self.columns[m.dest].push(tmp)
+        if m.src != m.dest {
+            let mut top = self.lift(m);
+            top.reverse();
+            self.columns[m.dest].append(&mut top);
         }
         Ok(())
     }
 
     fn run_fast(&mut self, m: &Move) -> Result<()> {
-        let start = self.columns[m.src].len() - m.count;
-        let items: Vec<_> = self.columns[m.src].drain(start..).collect();
-        self.columns[m.dest].extend(items);
+        let mut top = self.lift(m);
+        self.columns[m.dest].append(&mut top);
         Ok(())
     }
 }
 
+// Crates are matched to stacks by column: each has to be above exactly one stack number, so
+// labels can be any length and rows needn't be padded.
 impl FromStr for Crates {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        // last line is labels
-        let num_rows = s.lines().count() - 1;
-        let mut columns = Vec::new();
-        let mut expected_len = 0;
-        for (i, l) in s.lines().enumerate() {
-            if i == 0 {
-                if l.len() % 4 != 3 {
-                    return Err(anyhow!(
-                        "Line {} has length {} isn't 1 less than a multiple of 4",
-                        i,
-                        l.len()
-                    ));
-                }
-                expected_len = l.len();
-                let num_columns = l.len() / 4 + 1;
-                columns.extend((0..num_columns).map(|_| Vec::with_capacity(num_rows)))
+    type Err = Diagnostic;
+
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
+        let mut rows: Vec<_> = s.lines().collect();
+        let Some(labels) = rows.pop() else {
+            return Err(Diagnostic::at(s, s.len(), 0, "no stack labels"));
+        };
+        let mut found = Vec::new();
+        let stacks = stack_columns(s, labels, &mut found);
+        let mut columns: Vec<_> = (0..stacks.len())
+            .map(|_| Vec::with_capacity(rows.len()))
+            .collect();
+        for l in rows {
+            for (j, label) in row_crates(s, l, &stacks, &mut found) {
+                columns[j].push(label.to_owned())
             }
-            if l.len() != expected_len {
-                return Err(anyhow!("Line {} has length {}, expected", i, expected_len));
+        }
+        if let Some(d) = found.into_iter().next() {
+            return Err(d);
+        }
+        let columns = columns
+            .into_iter()
+            .map(|c: Vec<_>| c.into_iter().rev().collect())
+            .collect();
+        Ok(Self { columns })
+    }
+}
+
+// Each run of non-whitespace in `l`, with the character columns it covers
+fn words(l: &str) -> Vec<(Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut start = None;
+    let chars = l.char_indices().chain([(l.len(), ' ')]);
+    for (col, (pos, c)) in chars.enumerate() {
+        match start {
+            None if !c.is_whitespace() => start = Some((col, pos)),
+            Some((start_col, start_pos)) if c.is_whitespace() => {
+                found.push((start_col..col, &l[start_pos..pos]));
+                start = None;
             }
-            for (j, c) in l.chars().skip(1).step_by(4).enumerate() {
-                if c.is_numeric() {
-                    break;
-                }
-                if c.is_whitespace() {
-                    continue;
-                }
-                if !c.is_alphabetic() {
-                    return Err(anyhow!("Line {} stack {}: {:?} isn't alphabetic", i, j, c));
-                }
-                columns[j].push(c)
+            _ => {}
+        }
+    }
+    found
+}
+
+// The columns of each stack number on the label line. A number out of order is reported, but
+// still stands for the next stack.
+fn stack_columns(input: &str, l: &str, found: &mut Vec<Diagnostic>) -> Vec<Range<usize>> {
+    let mut stacks = Vec::new();
+    for (cols, word) in words(l) {
+        let want = (stacks.len() + 1).to_string();
+        if word != want {
+            found.push(Diagnostic::new(
+                input,
+                word,
+                format!("expected stack {want}"),
+            ));
+        }
+        stacks.push(cols);
+    }
+    stacks
+}
+
+// The labels of the crates drawn on row `l`, each with the stack it's above
+fn row_crates<'a>(
+    input: &str,
+    l: &'a str,
+    stacks: &[Range<usize>],
+    found: &mut Vec<Diagnostic>,
+) -> Vec<(usize, &'a str)> {
+    let mut crates: Vec<(usize, &str)> = Vec::new();
+    for (cols, word) in words(l) {
+        let label = word.strip_prefix('[').and_then(|w| w.strip_suffix(']'));
+        let Some(label) = label.filter(|label| !label.is_empty()) else {
+            found.push(Diagnostic::new(
+                input,
+                word,
+                format!("{word:?} isn't a crate"),
+            ));
+            continue;
+        };
+        if let Some((pos, c)) = label.char_indices().find(|(_, c)| !c.is_alphanumeric()) {
+            let msg = format!("{c:?} isn't alphanumeric");
+            found.push(Diagnostic::new(input, &label[pos..pos + c.len_utf8()], msg));
+            continue;
+        }
+        // The stacks' columns are in order and don't overlap, so the ones under it are a run
+        let start = stacks.partition_point(|s| s.end <= cols.start);
+        let mut above = start..stacks.partition_point(|s| s.start < cols.end);
+        let msg = match (above.next(), above.next()) {
+            (Some(j), None) if crates.last().is_some_and(|&(k, _)| k == j) => {
+                format!("stack {} already has a crate on this row", j + 1)
             }
+            (Some(j), None) => {
+                crates.push((j, label));
+                continue;
+            }
+            (None, _) => "crate isn't above a stack number".to_owned(),
+            (Some(_), Some(_)) => "crate is above more than one stack number".to_owned(),
+        };
+        found.push(Diagnostic::new(input, word, msg));
+    }
+    crates
+}
+
+// Checked by `aoc lint`, see src/lint.rs
+const ASSUMPTIONS: [Assumption; 3] = [
+    Assumption {
+        what: "crates are drawn as [label], each above exactly one stack number",
+        check: aligned_crates,
+    },
+    Assumption {
+        what: "stacks are numbered from 1 in order",
+        check: stack_labels,
+    },
+    Assumption {
+        what: "moves only use stacks that exist, and never take more crates than a stack has",
+        check: moves_in_bounds,
+    },
+];
+
+// Crate rows, the label row and the moves
+fn sections(input: &str) -> (Vec<&str>, Option<&str>, Vec<&str>) {
+    let mut lines = input.lines();
+    let mut drawing: Vec<_> = lines.by_ref().take_while(|l| !l.is_empty()).collect();
+    let labels = drawing.pop();
+    (drawing, labels, lines.collect())
+}
+
+fn aligned_crates(input: &str) -> Vec<Diagnostic> {
+    let (drawing, labels, _) = sections(input);
+    let stacks = stack_columns(input, labels.unwrap_or_default(), &mut Vec::new());
+    let mut found = Vec::new();
+    for l in drawing {
+        row_crates(input, l, &stacks, &mut found);
+    }
+    found
+}
+
+fn stack_labels(input: &str) -> Vec<Diagnostic> {
+    let Some(l) = sections(input).1 else {
+        return vec![Diagnostic::at(input, input.len(), 0, "no stack labels")];
+    };
+    let mut found = Vec::new();
+    stack_columns(input, l, &mut found);
+    found
+}
+
+fn moves_in_bounds(input: &str) -> Vec<Diagnostic> {
+    let (drawing, labels, moves) = sections(input);
+    let columns = stack_columns(input, labels.unwrap_or_default(), &mut Vec::new());
+    let stacks = columns.len();
+    let mut heights = vec![0; stacks];
+    for l in drawing {
+        for (j, _) in row_crates(input, l, &columns, &mut Vec::new()) {
+            heights[j] += 1
         }
-        for c in columns.iter_mut() {
-            c.reverse();
+    }
+    let mut found = Vec::new();
+    for l in moves {
+        let m = match l.parse::<Move>() {
+            Ok(m) => m,
+            Err(d) => {
+                found.push(d.within(input, l));
+                continue;
+            }
+        };
+        if m.src >= stacks || m.dest >= stacks {
+            let msg = format!("there are only {stacks} stacks");
+            found.push(Diagnostic::new(input, l, msg));
+            continue;
         }
-        Ok(Self { columns })
+        if m.count > heights[m.src] {
+            let msg = format!("stack {} only has {}", m.src + 1, heights[m.src]);
+            found.push(Diagnostic::new(input, l, msg));
+            continue;
+        }
+        heights[m.src] -= m.count;
+        heights[m.dest] += m.count;
+    }
+    found
+}
+
+impl Crates {
+    // Rows of the drawing, top first, then the stack numbers, padded to the full width
+    fn drawing(&self) -> Vec<String> {
+        let columns: Vec<Vec<_>> = self.columns.iter().map(|c| c.iter().collect()).collect();
+        drawing::crates(&columns)
+    }
+}
+
+// Exactly the input's drawing, so it parses back the same
+impl fmt::Display for Crates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.drawing().join("\n"))
     }
 }
 
-fn simulate(input: &str, run: fn(&mut Crates, &Move) -> Result<()>) -> Result<String> {
+// Drawn the same way as the input, tallest stack first, without trailing spaces on crate rows
+impl Frame for Crates {
+    fn draw(&self) -> String {
+        let mut lines = self.drawing();
+        let labels = lines.pop().unwrap_or_default();
+        let mut out = String::new();
+        for l in lines {
+            out += l.trim_end();
+            out.push('\n');
+        }
+        out += &labels;
+        out.push('\n');
+        out
+    }
+}
+
+fn simulate(input: &str, run: fn(&mut Crates, &Move) -> Result<()>) -> Result<Vec<String>> {
+    simulate_with(input, run, |_| {})
+}
+
+// Like simulate, also calling `on_step` with the starting crates and after every move
+fn simulate_with(
+    input: &str,
+    run: fn(&mut Crates, &Move) -> Result<()>,
+    mut on_step: impl FnMut(&Crates),
+) -> Result<Vec<String>> {
     const BLANK_UNIX: &str = "\n\n";
     const BLANK_WIN: &str = "\r\n\r\n";
     let (board_end, move_start) = if let Some(pos) = input.find(BLANK_UNIX) {
@@ -166,33 +374,46 @@ fn simulate(input: &str, run: fn(&mut Crates, &Move) -> Result<()>) -> Result<St
         return Err(anyhow!("Didn't find a blank line"));
     };
 
-    let mut crates: Crates = input[..board_end].parse()?;
+    let board = &input[..board_end];
+    let mut crates: Crates = board
+        .parse()
+        .map_err(|d: Diagnostic| d.within(input, board))?;
+    on_step(&crates);
 
-    for (i, l) in input[move_start..].lines().enumerate() {
-        let m: Move = l.parse().with_context(|| format!("Move {i}"))?;
+    for l in input[move_start..].lines() {
+        let m: Move = l.parse().map_err(|d: Diagnostic| d.within(input, l))?;
         crates
             .check_bounds(&m)
-            .with_context(|| format!("Move {i}"))?;
+            .map_err(|e| Diagnostic::new(input, l, e))?;
         run(&mut crates, &m)?;
+        on_step(&crates);
     }
 
-    let mut ret = "".to_owned();
-    for col in crates.columns {
-        if let Some(ch) = col.last() {
-            ret.push(*ch)
-        }
-    }
-    Ok(ret)
+    Ok(crates
+        .columns
+        .iter()
+        .filter_map(|c| c.last().cloned())
+        .collect())
+}
+
+fn frames(input: &str, run: fn(&mut Crates, &Move) -> Result<()>) -> Result<Vec<Crates>> {
+    let mut frames = Vec::new();
+    simulate_with(input, run, |c| frames.push(c.clone()))?;
+    Ok(frames)
 }
 
-fn part1(input: &str) -> Result<String> {
+// pub(crate) for benches/day05.rs, which includes this file
+#[instrument(skip_all)]
+pub(crate) fn part1(input: &str) -> Result<Vec<String>> {
     simulate(input, Crates::run_slow)
 }
 
-fn part2(input: &str) -> Result<String> {
+#[instrument(skip_all)]
+pub(crate) fn part2(input: &str) -> Result<Vec<String>> {
     simulate(input, Crates::run_fast)
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -203,6 +424,11 @@ fn solve(input: &str) -> Result<Answer> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022::{lint::lint, rng::Lcg};
+
+    fn letters(s: &str) -> Vec<String> {
+        s.chars().map(String::from).collect()
+    }
 
     #[test]
     fn example() {
@@ -210,8 +436,8 @@ mod tests {
         assert_eq!(
             answer,
             Answer {
-                part1: "CMZ".to_owned(),
-                part2: "MCD".to_owned()
+                part1: letters("CMZ"),
+                part2: letters("MCD")
             }
         );
     }
@@ -222,9 +448,188 @@ mod tests {
         assert_eq!(
             answer,
             Answer {
-                part1: "QNHWJVJZW".to_owned(),
-                part2: "BPCZJLFJW".to_owned()
+                part1: letters("QNHWJVJZW"),
+                part2: letters("BPCZJLFJW")
+            }
+        );
+    }
+
+    #[test]
+    fn test_ragged() {
+        let input = include_str!("../../data/challenge/day05.txt");
+        let trimmed: Vec<_> = input.lines().map(str::trim_end).collect();
+        assert_eq!(solve(&trimmed.join("\n")).unwrap(), solve(input).unwrap());
+    }
+
+    #[test]
+    fn test_long_labels() {
+        let input = "[Q]                                     [Z]   \n\
+            [AB] [C]                            [X] [YY]\n \
+            1   2   3   4   5   6   7   8   9  10  11\n\n\
+            move 2 from 11 to 3\n\
+            move 1 from 1 to 10\n";
+        let board = &input[..input.find("\n\n").unwrap()];
+        let crates: Crates = board.parse().unwrap();
+        let heights: Vec<_> = crates.columns.iter().map(Rope::len).collect();
+        assert_eq!(heights, [2, 1, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert!(crates.columns[10].iter().eq(["YY", "Z"]));
+        assert_eq!(crates.to_string().parse::<Crates>().unwrap(), crates);
+        let labels = |l: [&str; 4]| l.map(String::from).to_vec();
+        assert_eq!(
+            solve(input).unwrap(),
+            Answer {
+                part1: labels(["AB", "C", "YY", "Q"]),
+                part2: labels(["AB", "C", "Z", "Q"]),
+            }
+        );
+    }
+
+    // Big moves on a big drawing give the same as moving crates between Vecs
+    #[test]
+    fn test_generated() {
+        let (n, height) = (9, 2000);
+        let label = |x: usize, level: usize| format!("{:05}", x * height + level);
+        let mut input = String::new();
+        for level in (0..height).rev() {
+            let row: Vec<_> = (0..n).map(|x| format!("[{}]", label(x, level))).collect();
+            input += &row.join(" ");
+            input.push('\n');
+        }
+        let labels: Vec<_> = (1..=n).map(|i| format!("{i:^7}")).collect();
+        input += &labels.join(" ");
+        input += "\n\n";
+
+        let mut rng = Lcg::new(3);
+        let mut below = |n| rng.below(n);
+        let mut slow: Vec<Vec<_>> = (0..n)
+            .map(|x| (0..height).map(|level| label(x, level)).collect())
+            .collect();
+        let mut fast = slow.clone();
+        for _ in 0..1000 {
+            let (src, dest) = (below(n), below(n));
+            let count = below(slow[src].len() + 1);
+            input += &format!("move {count} from {} to {}\n", src + 1, dest + 1);
+            for _ in 0..count {
+                let c = slow[src].pop().unwrap();
+                slow[dest].push(c);
+            }
+            let start = fast[src].len() - count;
+            let top: Vec<_> = fast[src].drain(start..).collect();
+            fast[dest].extend(top);
+        }
+
+        let tops =
+            |stacks: Vec<Vec<String>>| stacks.into_iter().filter_map(|mut s| s.pop()).collect();
+        assert_eq!(
+            solve(&input).unwrap(),
+            Answer {
+                part1: tops(slow),
+                part2: tops(fast),
             }
         );
     }
+
+    #[test]
+    fn test_frames() {
+        let frames = frames(
+            include_str!("../../data/example/day05.txt"),
+            Crates::run_slow,
+        )
+        .unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(
+            frames[0].draw(),
+            "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3 \n"
+        );
+        assert_eq!(
+            frames[4].draw(),
+            "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 \n"
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for input in [
+            include_str!("../../data/example/day05.txt"),
+            include_str!("../../data/challenge/day05.txt"),
+        ] {
+            let drawing = &input[..input.find("\n\n").unwrap()];
+            let crates: Crates = drawing.parse().unwrap();
+            assert_eq!(crates.to_string(), drawing);
+            for run in [Crates::run_slow, Crates::run_fast] {
+                for c in frames(input, run).unwrap() {
+                    assert_eq!(c.to_string().parse::<Crates>().unwrap(), c, "{c}");
+                }
+            }
+        }
+        let last = frames(
+            include_str!("../../data/example/day05.txt"),
+            Crates::run_slow,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+        assert_eq!(
+            last.to_string(),
+            "        [Z]\n        [N]\n        [D]\n[C] [M] [P]\n 1   2   3 "
+        );
+        let empty = Crates {
+            columns: vec![Rope::new(), Rope::new()],
+        };
+        assert_eq!(empty.to_string(), " 1   2 ");
+        assert_eq!(empty.to_string().parse::<Crates>().unwrap(), empty);
+    }
+
+    #[test]
+    fn test_error_location() {
+        for (input, want) in [
+            (
+                "[a]\n 1 \n\nmove 1 from 1 to 1\nmove 1 from x to 1",
+                (5, 13),
+            ),
+            (
+                "[a]\n 1 \n\nmove 1 from 1 to 1\nmove 1 from 0 to 1",
+                (5, 13),
+            ),
+            ("[a]\n 1 \n\nmove 1 from 1 to 2", (4, 1)),
+            ("[a] [b]\n[c] [#]\n 1   2 \n\nmove 1 from 1 to 2", (2, 6)),
+        ] {
+            let err = part1(input).unwrap_err();
+            let d = err.downcast_ref::<Diagnostic>().unwrap();
+            assert_eq!((d.line, d.column), want, "{d}");
+        }
+    }
+
+    #[test]
+    fn test_assumptions() {
+        for input in [
+            include_str!("../../data/example/day05.txt"),
+            include_str!("../../data/challenge/day05.txt"),
+        ] {
+            for (a, found) in lint(input, &ASSUMPTIONS) {
+                assert_eq!(found, [], "{}", a.what)
+            }
+        }
+
+        // A crate over two stacks, something that isn't a crate, a bad label, a misnumbered stack,
+        // then moving too much and from a missing stack
+        let input = "[ABCDE]  [C] x\n    [#]\n 1   3   3\n\n\
+            move 1 from 3 to 1\n\
+            move 3 from 1 to 2\n\
+            move 1 from 4 to 1\n";
+        let found: Vec<Vec<_>> = lint(input, &ASSUMPTIONS)
+            .into_iter()
+            .map(|(_, found)| found.iter().map(|d| (d.line, d.column)).collect())
+            .collect();
+        assert_eq!(
+            found,
+            [
+                vec![(1, 1), (1, 14), (2, 6)],
+                vec![(3, 6)],
+                vec![(6, 1), (7, 1)]
+            ]
+        );
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day05.txt"), solve);
 }
@@ -1,24 +1,41 @@
-use std::cmp::max;
-use std::str::FromStr;
-
-use anyhow::{anyhow, bail, ensure, Context, Result};
-use nom::branch::alt;
-use nom::character::complete::{alpha1, char as nom_char, digit1, line_ending, multispace0};
-use nom::combinator::{all_consuming, map, map_res, value};
-use nom::error::{convert_error, VerboseError};
-use nom::multi::{count, separated_list1};
-use nom::Finish;
+use std::{cmp::max, fmt, ops::Range};
+
+use anyhow::{bail, ensure, Context, Result};
+use aoc_2022::cli::has_flag;
+use aoc_2022::drawing;
+use aoc_2022::nom_util::{lines, run_parser, uint, IResult};
+use nom::character::complete::{alphanumeric1, char as nom_char, digit1, line_ending, space0};
+use nom::combinator::map_res;
+use nom::multi::{many0, many1};
+use nom::sequence::{delimited, pair, terminated};
 use nom::{bytes::complete::tag, sequence::tuple};
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
-    part1: String,
-    part2: String,
+    part1: Vec<String>,
+    part2: Vec<String>,
 }
 
 fn main() -> Result<()> {
     let d = include_str!("../../data/challenge/day05.txt");
     println!("{:#?}", solve(d)?);
+    if has_flag("--cranes") {
+        let cranes: [&dyn Crane; 4] = [
+            &CrateMover9000,
+            &CrateMover9001,
+            &Capacity::new(3)?,
+            &KeepsOrderUpTo(3),
+        ];
+        for crane in cranes {
+            let report = simulate(d, crane)?;
+            println!(
+                "{}: {} ({})",
+                crane.name(),
+                report.tops.join(" "),
+                report.cost
+            );
+        }
+    }
     Ok(())
 }
 
@@ -31,13 +48,11 @@ struct Move {
 
 #[derive(Debug, PartialEq, Eq)]
 struct Crates {
-    columns: Vec<Vec<char>>,
+    columns: Vec<Vec<String>>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct CrateLine {
-    line: Vec<Option<char>>,
-}
+// The character columns a crate or stack number covers, and its label or number
+type Cell<'a> = (Range<usize>, &'a str);
 
 impl Move {
     fn from_parsed((count, src, dest): (usize, usize, usize)) -> Result<Self> {
@@ -50,24 +65,39 @@ impl Move {
 }
 
 impl Crates {
-    fn from_crate_lines(lines: Vec<CrateLine>) -> Result<Self> {
-        let expected_len = lines[0].line.len();
-        for (i, l) in lines.iter().enumerate() {
-            if l.line.len() != expected_len {
-                bail!(
-                    "Crate line {i} had {} entries, expected {expected_len}",
-                    l.line.len()
-                );
-            }
+    // Each crate goes on the stack whose number it's above
+    fn from_aligned((rows, labels): (Vec<Vec<Cell>>, Vec<Cell>)) -> Result<Self> {
+        for (i, (_, label)) in labels.iter().enumerate() {
+            ensure!(
+                label.parse() == Ok(i + 1),
+                "Stack {} is numbered {label}",
+                i + 1
+            );
         }
-        let mut columns: Vec<Vec<char>> = (0..expected_len)
-            .map(|_| Vec::with_capacity(lines.len()))
+        let mut columns: Vec<Vec<String>> = (0..labels.len())
+            .map(|_| Vec::with_capacity(rows.len()))
             .collect();
-        for l in lines.iter().rev() {
-            for (i, a_crate) in l.line.iter().enumerate() {
-                if let Some(ch) = a_crate {
-                    columns[i].push(*ch)
-                }
+        for (i, row) in rows.iter().enumerate().rev() {
+            let mut last = None;
+            for (cols, a_crate) in row {
+                // Labels are in order and don't overlap, so the ones under it are a run
+                let start = labels.partition_point(|(l, _)| l.end <= cols.start);
+                let mut above = start..labels.partition_point(|(l, _)| l.start < cols.end);
+                let j = match (above.next(), above.next()) {
+                    (Some(j), None) => j,
+                    (None, _) => bail!("Crate {a_crate} on line {} isn't above a stack", i + 1),
+                    (Some(_), Some(_)) => {
+                        bail!("Crate {a_crate} on line {} is above two stacks", i + 1)
+                    }
+                };
+                ensure!(
+                    last != Some(j),
+                    "Stack {} has two crates on line {}",
+                    j + 1,
+                    i + 1
+                );
+                last = Some(j);
+                columns[j].push(a_crate.to_string())
             }
         }
         Ok(Self { columns })
@@ -95,7 +125,8 @@ impl Crates {
         Ok(())
     }
 
-    fn run_move(&mut self, m: &Move, mover: Mover) {
+    fn run_move(&mut self, m: &Move, crane: &dyn Crane, cost: &mut Cost) {
+        cost.moves += 1;
         if m.src == m.dest {
             return;
         }
@@ -105,106 +136,204 @@ impl Crates {
         } else {
             (&mut b[0], &mut a[m.dest])
         };
-        mover(src, dest, m.count)
+        let size = crane.lift_size(m.count);
+        let mut left = m.count;
+        while left > 0 {
+            let n = size.clamp(1, left);
+            dest.extend(src.drain((src.len() - n)..));
+            left -= n;
+            cost.lifts += 1;
+            cost.crates += n;
+            cost.distance += m.src.abs_diff(m.dest);
+        }
+    }
+}
+
+// The drawing crates_section parses, without the final line ending
+impl fmt::Display for Crates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", drawing::crates(&self.columns).join("\n"))
     }
 }
 
-type Mover = fn(&mut Vec<char>, &mut Vec<char>, usize);
+// A crane moves crates as a series of lifts, each taking some crates off the top of the source
+// stack and putting them down on the destination in the same order.
+trait Crane {
+    fn name(&self) -> String;
+
+    // How many crates each lift takes to move `count` of them, the last taking whatever's left
+    fn lift_size(&self, count: usize) -> usize;
+}
+
+// Part 1's crane, one crate at a time
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
 
-fn move_slow(src: &mut Vec<char>, dest: &mut Vec<char>, count: usize) {
-    dest.extend(src.drain((src.len() - count)..).rev());
+    fn lift_size(&self, _: usize) -> usize {
+        1
+    }
 }
 
-fn move_fast(src: &mut Vec<char>, dest: &mut Vec<char>, count: usize) {
-    dest.extend(src.drain((src.len() - count)..));
+// Part 2's crane, the whole move at once
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn lift_size(&self, count: usize) -> usize {
+        count
+    }
 }
 
-type IResult<'a, T> = nom::IResult<&'a str, T, VerboseError<&'a str>>;
+// Lifts at most this many crates at once, so bigger moves take several lifts from the top
+struct Capacity(usize);
 
-fn a_crate(input: &str) -> IResult<Option<char>> {
-    alt((
-        map(
-            tuple((nom_char::<&str, _>('['), alpha1, nom_char(']'))),
-            |(_, items, _)| Some(items.chars().next().unwrap()),
-        ),
-        value(None, count(nom_char(' '), 3)),
-    ))(input)
+impl Capacity {
+    fn new(max: usize) -> Result<Self> {
+        ensure!(max > 0, "capacity was {max}, must be positive");
+        Ok(Self(max))
+    }
+}
+
+impl Crane for Capacity {
+    fn name(&self) -> String {
+        format!("capacity {}", self.0)
+    }
+
+    fn lift_size(&self, _: usize) -> usize {
+        self.0
+    }
+}
+
+// Moves up to this many crates at once, but any more one at a time
+struct KeepsOrderUpTo(usize);
+
+impl Crane for KeepsOrderUpTo {
+    fn name(&self) -> String {
+        format!("keeps order up to {}", self.0)
+    }
+
+    fn lift_size(&self, count: usize) -> usize {
+        if count <= self.0 {
+            count
+        } else {
+            1
+        }
+    }
+}
+
+// Distance is how many stacks along each lift carries its crates
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Cost {
+    moves: usize,
+    lifts: usize,
+    crates: usize,
+    distance: usize,
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} moves in {} lifts, {} crates carried {} stacks in all",
+            self.moves, self.lifts, self.crates, self.distance
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Report {
+    tops: Vec<String>,
+    cost: Cost,
+}
+
+fn a_crate(input: &str) -> IResult<'_, &str> {
+    delimited(nom_char('['), alphanumeric1, nom_char(']'))(input)
+}
+
+// Where each item on a line starts and ends, counting the spaces before it
+fn columns<'a>(items: Vec<(&str, &'a str)>, extra: usize) -> Vec<Cell<'a>> {
+    let mut col = 0;
+    items
+        .into_iter()
+        .map(|(gap, item)| {
+            col += gap.chars().count();
+            let start = col;
+            col += item.chars().count() + extra;
+            (start..col, item)
+        })
+        .collect()
 }
 
-fn crate_line(input: &str) -> IResult<CrateLine> {
-    let (input, line) = separated_list1(nom_char(' '), a_crate)(input)?;
-    Ok((input, CrateLine { line }))
+// Crates at any column, with any spaces between and after them
+fn crate_line(input: &str) -> IResult<'_, Vec<Cell<'_>>> {
+    let (input, items) = terminated(many0(pair(space0, a_crate)), space0)(input)?;
+    Ok((input, columns(items, 2)))
 }
 
-fn label_line(input: &str) -> IResult<()> {
-    let (input, _) =
-        separated_list1(nom_char(' '), tuple((nom_char(' '), digit1, nom_char(' '))))(input)?;
-    Ok((input, ()))
+fn label_line(input: &str) -> IResult<'_, Vec<Cell<'_>>> {
+    let (input, items) = terminated(many1(pair(space0, digit1)), space0)(input)?;
+    Ok((input, columns(items, 0)))
 }
 
-fn crates_section(input: &str) -> IResult<Crates> {
-    let (input, (crates, _, _, _)) = tuple((
+fn crates_section(input: &str) -> IResult<'_, Crates> {
+    let (input, (crates, _)) = tuple((
         map_res(
-            separated_list1(line_ending, crate_line),
-            Crates::from_crate_lines,
+            pair(many0(terminated(crate_line, line_ending)), label_line),
+            Crates::from_aligned,
         ),
         line_ending,
-        label_line,
-        line_ending,
     ))(input)?;
     Ok((input, crates))
 }
 
-fn a_move(input: &str) -> IResult<(usize, usize, usize)> {
-    let (input, (_, count, _, src, _, dest)) = tuple((
-        tag("move "),
-        map_res(digit1, usize::from_str),
-        tag(" from "),
-        map_res(digit1, usize::from_str),
-        tag(" to "),
-        map_res(digit1, usize::from_str),
-    ))(input)?;
+fn a_move(input: &str) -> IResult<'_, (usize, usize, usize)> {
+    let (input, (_, count, _, src, _, dest)) =
+        tuple((tag("move "), uint, tag(" from "), uint, tag(" to "), uint))(input)?;
     Ok((input, (count, src, dest)))
 }
 
-fn parse_input(input: &str) -> IResult<(Crates, Vec<Move>)> {
-    let (input, (crates, _, moves, _)) = all_consuming(tuple((
+fn parse_input(input: &str) -> IResult<'_, (Crates, Vec<Move>)> {
+    let (input, (crates, _, moves)) = tuple((
         crates_section,
         line_ending,
-        separated_list1(line_ending, map_res(a_move, Move::from_parsed)),
-        multispace0,
-    )))(input)?;
+        lines(map_res(a_move, Move::from_parsed)),
+    ))(input)?;
     Ok((input, (crates, moves)))
 }
 
-fn simulate(input: &str, mover: Mover) -> Result<String> {
-    // Force stringification of error to avoid pain of lifetime
-    let (_, (mut crates, moves)) = parse_input(input)
-        .finish()
-        .map_err(|e| anyhow!("Parse error: {}", convert_error(input, e)))?;
+fn simulate(input: &str, crane: &dyn Crane) -> Result<Report> {
+    let (mut crates, moves) = run_parser(parse_input, input)?;
 
+    let mut cost = Cost::default();
     for (i, m) in moves.iter().enumerate() {
         crates
             .check_bounds(m)
             .with_context(|| format!("Move {i}"))?;
-        crates.run_move(m, mover);
+        crates.run_move(m, crane, &mut cost);
     }
 
-    let mut ret = "".to_owned();
-    for col in crates.columns {
-        if let Some(ch) = col.last() {
-            ret.push(*ch)
-        }
-    }
-    Ok(ret)
+    let tops = crates
+        .columns
+        .into_iter()
+        .filter_map(|mut c| c.pop())
+        .collect();
+    Ok(Report { tops, cost })
 }
 
-fn part1(input: &str) -> Result<String> {
-    simulate(input, move_slow)
+// pub(crate) for benches/day05.rs, which includes this file
+pub(crate) fn part1(input: &str) -> Result<Vec<String>> {
+    Ok(simulate(input, &CrateMover9000)?.tops)
 }
 
-fn part2(input: &str) -> Result<String> {
-    simulate(input, move_fast)
+pub(crate) fn part2(input: &str) -> Result<Vec<String>> {
+    Ok(simulate(input, &CrateMover9001)?.tops)
 }
 
 fn solve(input: &str) -> Result<Answer> {
@@ -218,14 +347,18 @@ fn solve(input: &str) -> Result<Answer> {
 mod tests {
     use super::*;
 
+    fn letters(s: &str) -> Vec<String> {
+        s.chars().map(String::from).collect()
+    }
+
     #[test]
     fn example() {
         let answer = solve(include_str!("../../data/example/day05.txt")).unwrap();
         assert_eq!(
             answer,
             Answer {
-                part1: "CMZ".to_owned(),
-                part2: "MCD".to_owned()
+                part1: letters("CMZ"),
+                part2: letters("MCD")
             }
         );
     }
@@ -236,48 +369,129 @@ mod tests {
         assert_eq!(
             answer,
             Answer {
-                part1: "QNHWJVJZW".to_owned(),
-                part2: "BPCZJLFJW".to_owned()
+                part1: letters("QNHWJVJZW"),
+                part2: letters("BPCZJLFJW")
             }
         );
     }
 
+    #[test]
+    fn test_cranes() {
+        let input = include_str!("../../data/example/day05.txt");
+        let cost = |moves, lifts, crates, distance| Cost {
+            moves,
+            lifts,
+            crates,
+            distance,
+        };
+        for (crane, tops, want) in [
+            (&CrateMover9000 as &dyn Crane, "CMZ", cost(4, 7, 7, 10)),
+            (&CrateMover9001, "MCD", cost(4, 4, 7, 5)),
+            (&Capacity::new(2).unwrap(), "MCZ", cost(4, 5, 7, 7)),
+            (&KeepsOrderUpTo(2), "MCZ", cost(4, 6, 7, 9)),
+        ] {
+            let report = simulate(input, crane).unwrap();
+            assert_eq!(report.tops, letters(tops), "{}", crane.name());
+            assert_eq!(report.cost, want, "{}", crane.name());
+        }
+
+        // The extremes of each model are the two CrateMovers
+        let input = include_str!("../../data/challenge/day05.txt");
+        let (slow, fast) = (part1(input).unwrap(), part2(input).unwrap());
+        for (crane, want) in [
+            (&Capacity::new(1).unwrap() as &dyn Crane, &slow),
+            (&Capacity::new(100).unwrap(), &fast),
+            (&KeepsOrderUpTo(1), &slow),
+            (&KeepsOrderUpTo(100), &fast),
+        ] {
+            assert_eq!(
+                &simulate(input, crane).unwrap().tops,
+                want,
+                "{}",
+                crane.name()
+            );
+        }
+        assert!(Capacity::new(0).is_err());
+
+        let mut crates = Crates {
+            columns: vec![(0..7).map(|i| i.to_string()).collect(), Vec::new()],
+        };
+        let mut cost = Cost::default();
+        let m = Move {
+            count: 7,
+            src: 0,
+            dest: 1,
+        };
+        crates.run_move(&m, &Capacity::new(3).unwrap(), &mut cost);
+        assert_eq!(cost.lifts, 3);
+        assert_eq!(crates.columns[1], ["4", "5", "6", "1", "2", "3", "0"]);
+    }
+
     #[test]
     fn test_parse_crates() {
-        let (leftover, ch) = a_crate("[a]").unwrap();
+        let (leftover, label) = a_crate("[a]").unwrap();
         assert_eq!(leftover, "");
-        assert_eq!(ch, Some('a'));
+        assert_eq!(label, "a");
 
-        let (leftover, ch) = a_crate("   ").unwrap();
+        let (leftover, label) = a_crate("[AB1]").unwrap();
         assert_eq!(leftover, "");
-        assert_eq!(ch, None);
+        assert_eq!(label, "AB1");
+
+        assert!(a_crate("   ").is_err());
 
         let (leftover, line) = crate_line("[a]").unwrap();
         assert_eq!(leftover, "");
-        assert_eq!(
-            line,
-            CrateLine {
-                line: vec![Some('a')]
-            }
-        );
+        assert_eq!(line, [(0..3, "a")]);
 
         let (leftover, line) = crate_line("    [a] [b]").unwrap();
         assert_eq!(leftover, "");
-        assert_eq!(
-            line,
-            CrateLine {
-                line: vec![None, Some('a'), Some('b')]
-            }
-        );
+        assert_eq!(line, [(4..7, "a"), (8..11, "b")]);
+
+        let (leftover, line) = crate_line("[ab]  [c]   ").unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(line, [(0..4, "ab"), (6..9, "c")]);
+
+        let (leftover, line) = crate_line("  ").unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(line, []);
     }
 
     #[test]
     fn test_parse_labels() {
-        let (leftover, ()) = label_line(" 1 ").unwrap();
+        let (leftover, labels) = label_line(" 1 ").unwrap();
+        assert_eq!(leftover, "");
+        assert_eq!(labels, [(1..2, "1")]);
+
+        let (leftover, labels) = label_line(" 1   2 ").unwrap();
         assert_eq!(leftover, "");
+        assert_eq!(labels, [(1..2, "1"), (5..6, "2")]);
 
-        let (leftover, ()) = label_line(" 1   2 ").unwrap();
+        let (leftover, labels) = label_line(" 9  10").unwrap();
         assert_eq!(leftover, "");
+        assert_eq!(labels, [(1..2, "9"), (4..6, "10")]);
+    }
+
+    #[test]
+    fn test_long_labels() {
+        let input = "[Q]                                     [Z]   \n\
+            [AB] [C]                            [X] [YY]\n \
+            1   2   3   4   5   6   7   8   9  10  11\n\n\
+            move 2 from 11 to 3\n\
+            move 1 from 1 to 10\n";
+        let (_, crates) = crates_section(input).unwrap();
+        let heights: Vec<_> = crates.columns.iter().map(Vec::len).collect();
+        assert_eq!(heights, [2, 1, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(crates.columns[10], ["YY", "Z"]);
+        let drawing = format!("{crates}\n");
+        assert_eq!(run_parser(crates_section, &drawing).unwrap(), crates);
+        let labels = |l: [&str; 4]| l.map(String::from).to_vec();
+        assert_eq!(
+            solve(input).unwrap(),
+            Answer {
+                part1: labels(["AB", "C", "YY", "Q"]),
+                part2: labels(["AB", "C", "Z", "Q"]),
+            }
+        );
     }
 
     #[test]
@@ -303,9 +517,40 @@ mod tests {
         assert_eq!(
             crates,
             Crates {
-                columns: vec![vec!['a']]
+                columns: vec![vec!["a".to_owned()]]
             }
         );
+
+        // Misnumbered stacks, a crate over two stacks and one over none
+        for drawing in [
+            "[a]\n 2 \n",
+            "[a]\n 1   3\n",
+            "[abc]\n 1  2\n",
+            "      [a]\n 1   2\n",
+        ] {
+            assert!(run_parser(crates_section, drawing).is_err(), "{drawing:?}");
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for input in [
+            include_str!("../../data/example/day05.txt"),
+            include_str!("../../data/challenge/day05.txt"),
+        ] {
+            let (rest, mut crates) = crates_section(input).unwrap();
+            let drawing = &input[..input.len() - rest.len()];
+            assert_eq!(format!("{crates}\n"), drawing);
+
+            let (_, moves) = run_parser(parse_input, input).unwrap();
+            for m in &moves {
+                crates.run_move(m, &CrateMover9001, &mut Cost::default());
+                let drawing = format!("{crates}\n");
+                let (rest, parsed) = crates_section(&drawing).unwrap();
+                assert_eq!(rest, "");
+                assert_eq!(parsed, crates);
+            }
+        }
     }
 
     #[test]
@@ -321,7 +566,7 @@ move 1 from 1 to 1",
         assert_eq!(
             crates,
             Crates {
-                columns: vec![vec!['a']]
+                columns: vec![vec!["a".to_owned()]]
             }
         );
         assert_eq!(
@@ -333,4 +578,6 @@ move 1 from 1 to 1",
             }]
         );
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day05.txt"), solve);
 }
@@ -1,4 +1,6 @@
 use anyhow::{bail, Result};
+use aoc_2022::trace::trace_arg;
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -7,6 +9,7 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day06.txt");
     println!("{:#?}", solve(d)?);
     Ok(())
@@ -16,7 +19,7 @@ fn find_distinct(input: &str, win_size: usize) -> Result<usize> {
     let mut checks = 0;
     let input: Vec<_> = input.chars().collect();
     let mut idx = 0;
-    'outer: while idx <= input.len() - win_size {
+    'outer: while idx + win_size <= input.len() {
         let win = &input[idx..idx + win_size];
         for (i, c) in win.iter().enumerate().rev() {
             if win[i + 1..].iter().any(|d| {
@@ -33,14 +36,17 @@ fn find_distinct(input: &str, win_size: usize) -> Result<usize> {
     bail!("No starting point found")
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<usize> {
     find_distinct(input, 4)
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     find_distinct(input, 14)
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -77,4 +83,6 @@ mod tests {
             }
         );
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day06.txt"), solve);
 }
@@ -12,6 +12,10 @@ fn main() {
 }
 
 fn find_distinct(input: &str, win_size: usize) -> Option<usize> {
+    // Counts are indexed by letter, so anything else has no answer
+    if !input.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
     input
         .as_bytes()
         .windows(win_size)
@@ -75,4 +79,6 @@ mod tests {
             }
         )
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day06.txt"), solve);
 }
@@ -45,6 +45,10 @@ impl LowerMultiSet {
 }
 
 fn find_distinct(input: &str, win_size: usize) -> Option<usize> {
+    // Counts are indexed by letter, so anything else has no answer
+    if !input.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
     let mut set = LowerMultiSet::new();
     for (i, w) in input.as_bytes().windows(win_size).enumerate() {
         if i == 0 {
@@ -111,4 +115,6 @@ mod tests {
             }
         )
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day06.txt"), solve);
 }
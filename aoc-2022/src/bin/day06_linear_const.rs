@@ -1,6 +1,5 @@
 // This is technically O(n) assuming alphabet is constant
-// Need nightly for const generic windows()
-#![feature(array_windows)]
+// day06_linear with the window size as a const generic, for array_windows
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -50,6 +49,10 @@ impl LowerMultiSet {
 }
 
 fn find_distinct<const N: usize>(input: &str) -> Option<usize> {
+    // Counts are indexed by letter, so anything else has no answer
+    if !input.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
     let mut set = LowerMultiSet::new();
     for (i, w) in input.as_bytes().array_windows::<N>().enumerate() {
         if i == 0 {
@@ -116,4 +119,6 @@ mod tests {
             }
         )
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day06.txt"), solve);
 }
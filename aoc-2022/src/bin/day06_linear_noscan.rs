@@ -51,6 +51,10 @@ impl LowerMultiSet {
 }
 
 fn find_distinct(input: &str, win_size: usize) -> Option<usize> {
+    // Counts are indexed by letter, so anything else has no answer
+    if !input.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
     let mut set = LowerMultiSet::default();
     for (i, w) in input.as_bytes().windows(win_size).enumerate() {
         if i == 0 {
@@ -117,4 +121,6 @@ mod tests {
             }
         )
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day06.txt"), solve);
 }
@@ -83,4 +83,6 @@ mod tests {
             }
         );
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day06.txt"), solve);
 }
@@ -1,15 +1,19 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
 use anyhow::{anyhow, ensure, Result};
+use aoc_2022::fs_tree::FsTree;
+use aoc_2022::nom_util::{lines, run_parser, uint, IResult};
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{checked, solver};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while},
-    character::complete::{alpha1, char, digit1, line_ending},
-    combinator::{all_consuming, map, map_res, value},
-    multi::{separated_list0, separated_list1},
-    sequence::tuple,
-    Finish,
+    character::complete::{alpha1, char, line_ending},
+    combinator::{map, value},
+    multi::separated_list0,
+    sequence::{preceded, separated_pair},
 };
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -18,15 +22,13 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     println!(
         "{:#?}",
         solve(include_str!("../../data/challenge/day07.txt"))?
     );
     Ok(())
 }
-type IError<'a> = nom::error::VerboseError<&'a str>;
-
-type IResult<'a, T> = nom::IResult<&'a str, T, IError<'a>>;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum DirEntry {
@@ -42,145 +44,111 @@ enum Cmd {
     Ls(Vec<DirEntry>),
 }
 
-fn dir_entry(input: &str) -> IResult<DirEntry> {
+fn dir_entry(input: &str) -> IResult<'_, DirEntry> {
     alt((
-        map_res(
-            tuple((
-                digit1::<&str, IError>,
+        map(
+            separated_pair(
+                uint,
                 char(' '),
                 take_while(|c: char| c == '.' || c.is_alphanumeric()),
-            )),
-            |(s, _, n)| Ok::<DirEntry, anyhow::Error>(DirEntry::File(s.parse()?, n.to_owned())),
+            ),
+            |(s, n)| DirEntry::File(s, n.to_owned()),
         ),
-        map_res(tuple((tag("dir "), alpha1::<&str, IError>)), |(_, n)| {
-            Ok::<DirEntry, anyhow::Error>(DirEntry::Dir(n.to_owned()))
+        map(preceded(tag("dir "), alpha1), |n: &str| {
+            DirEntry::Dir(n.to_owned())
         }),
     ))(input)
 }
 
-fn cmd_up(input: &str) -> IResult<Cmd> {
+fn cmd_up(input: &str) -> IResult<'_, Cmd> {
     value(Cmd::Up, tag("cd .."))(input)
 }
 
-fn cmd_to_top(input: &str) -> IResult<Cmd> {
+fn cmd_to_top(input: &str) -> IResult<'_, Cmd> {
     value(Cmd::ToTop, tag("cd /"))(input)
 }
 
-fn cmd_down(input: &str) -> IResult<Cmd> {
-    map(tuple((tag("cd "), alpha1::<&str, IError>)), |(_, d)| {
+fn cmd_down(input: &str) -> IResult<'_, Cmd> {
+    map(preceded(tag("cd "), alpha1), |d: &str| {
         Cmd::Down(d.to_owned())
     })(input)
 }
 
-fn cmd_ls(input: &str) -> IResult<Cmd> {
+fn cmd_ls(input: &str) -> IResult<'_, Cmd> {
     map(
-        tuple((
+        preceded(
             tag("ls"),
-            line_ending,
-            separated_list0(line_ending, dir_entry),
-        )),
-        |(_, _, e)| Cmd::Ls(e),
+            preceded(line_ending, separated_list0(line_ending, dir_entry)),
+        ),
+        Cmd::Ls,
     )(input)
 }
 
-fn cmd_line(input: &str) -> IResult<Cmd> {
-    map(
-        tuple((tag("$ "), alt((cmd_up, cmd_to_top, cmd_down, cmd_ls)))),
-        |(_, c)| c,
-    )(input)
+fn cmd_line(input: &str) -> IResult<'_, Cmd> {
+    preceded(tag("$ "), alt((cmd_up, cmd_to_top, cmd_down, cmd_ls)))(input)
 }
 
-fn shell_session(input: &str) -> Result<Vec<Cmd>> {
-    all_consuming(separated_list1(line_ending, cmd_line))(input)
-        .finish()
-        .map(|(_, c)| c)
-        .map_err(|e| anyhow!("{e}"))
+fn session(input: &str) -> IResult<'_, Vec<Cmd>> {
+    lines(cmd_line)(input)
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct SubDirs(Vec<String>);
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct DirTree {
-    tree: HashMap<String, (usize, SubDirs, bool)>,
-}
-
-impl DirTree {
-    fn from_session(session: &Vec<Cmd>) -> Result<Self> {
-        let mut tree = HashMap::new();
-        let mut cur_path = Vec::new();
-        for cmd in session {
-            match cmd {
-                Cmd::ToTop => {
-                    cur_path = vec!["".to_owned()];
-                    tree.insert("".to_owned(), (0, SubDirs(Vec::new()), false));
-                }
-                Cmd::Down(d) => {
-                    cur_path.push(d.clone());
-                    let cur_name = cur_path.join("/");
-                    tree.entry(cur_name)
-                        .or_insert((0, SubDirs(Vec::new()), false));
-                }
-                Cmd::Up => {
-                    ensure!(cur_path.pop().is_some(), "cd .. but nothing above")
-                }
-                Cmd::Ls(entries) => {
-                    let cur_name = cur_path.join("/");
-                    let (total, sub_dirs, visited) = tree
-                        .get_mut(&cur_name)
-                        .ok_or_else(|| anyhow!("ls in unknown_dir {cur_name:?}"))?;
-
-                    ensure!(!*visited, "already visited {cur_name:?}");
-                    *visited = true;
-
-                    for ent in entries {
-                        match ent {
-                            DirEntry::Dir(sub) => {
-                                let sub_name = cur_path.join("/") + "/" + sub;
-                                sub_dirs.0.push(sub_name)
-                            }
-                            DirEntry::File(size, _) => *total += size,
-                        }
-                    }
+fn shell_session(input: &str) -> Result<Vec<Cmd>> {
+    run_parser(session, input)
+}
+
+#[instrument(skip_all)]
+fn build_tree(session: &Vec<Cmd>) -> Result<FsTree> {
+    let mut tree = FsTree::new();
+    let mut visited = HashSet::new();
+    let mut cur = tree.root();
+    for cmd in session {
+        match cmd {
+            Cmd::ToTop => cur = tree.root(),
+            Cmd::Down(d) => cur = tree.add_dir(cur, d)?,
+            Cmd::Up => {
+                cur = tree
+                    .parent(cur)
+                    .ok_or_else(|| anyhow!("cd .. but nothing above"))?
+            }
+            Cmd::Ls(entries) => {
+                ensure!(visited.insert(cur), "already visited {:?}", tree.path(cur));
+                for ent in entries {
+                    match ent {
+                        DirEntry::Dir(sub) => tree.add_dir(cur, sub)?,
+                        DirEntry::File(size, name) => tree.add_file(cur, name, *size)?,
+                    };
                 }
             }
         }
-        Ok(Self { tree })
-    }
-
-    fn trans_totals(&self, root: &str) -> Result<(usize, Vec<usize>)> {
-        let mut totals = Vec::new();
-        let (mut my_size, subs, _) = self
-            .tree
-            .get(root)
-            .ok_or_else(|| anyhow!("no dir {root:?}"))?;
-        for d in subs.0.iter() {
-            let (s, sub_totals) = self.trans_totals(d)?;
-            my_size += s;
-            totals.extend(sub_totals)
-        }
-        totals.push(my_size);
-        Ok((my_size, totals))
     }
+    Ok(tree)
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<usize> {
     let sess = shell_session(input)?;
-    let tree = DirTree::from_session(&sess)?;
-    let (_, totals) = tree.trans_totals("")?;
-    Ok(totals.iter().filter(|t| t <= &&100000).sum())
+    let tree = build_tree(&sess)?;
+    let small = tree.dirs().map(|d| tree.size(d)).filter(|t| t <= &100000);
+    Ok(checked::sum(solver!(), 0, small)?)
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     let sess = shell_session(input)?;
-    let tree = DirTree::from_session(&sess)?;
-    let (top_total, totals) = tree.trans_totals("")?;
+    let tree = build_tree(&sess)?;
+    let top_total = tree.size(tree.root());
     let (disk_size, req_size) = (70000000, 30000000);
-    let to_free = (top_total + req_size).saturating_sub(disk_size);
+    let to_free = checked::add(solver!(), top_total, req_size)?.saturating_sub(disk_size);
     ensure!(to_free > 0, "No need to free");
-    Ok(*totals.iter().filter(|s| s > &&to_free).min().unwrap())
+    Ok(tree
+        .dirs()
+        .map(|d| tree.size(d))
+        .filter(|s| s > &to_free)
+        .min()
+        .unwrap())
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -231,4 +199,31 @@ mod tests {
             assert_eq!(got, want, "wrong out for input {input:?}")
         }
     }
+
+    #[test]
+    fn test_overflow() {
+        let input = format!("$ cd /\n$ ls\n{} a\n1 b\n", usize::MAX);
+        let err = solve(&input).unwrap_err();
+        assert_eq!(err.to_string(), "size of \"/\" overflows");
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let sess = shell_session(include_str!("../../data/example/day07.txt")).unwrap();
+        let tree = build_tree(&sess).unwrap();
+        for (path, want) in [
+            ("/", 48381165),
+            ("/a", 94853),
+            ("/a/e", 584),
+            ("/d", 24933642),
+        ] {
+            assert_eq!(tree.size(tree.lookup(path).unwrap()), want, "{path}")
+        }
+        assert_eq!(tree.size(tree.lookup("/d/d.log").unwrap()), 8033020);
+
+        let sess = shell_session("$ cd /\n$ ls\ndir a\n$ ls\n1 b").unwrap();
+        assert!(build_tree(&sess).is_err());
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day07.txt"), solve);
 }
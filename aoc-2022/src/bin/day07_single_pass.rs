@@ -1,13 +1,14 @@
-use anyhow::{anyhow, ensure, Result};
+use anyhow::{ensure, Context, Result};
+use aoc_2022::checked::{self, Overflow};
+use aoc_2022::nom_util::{line_end_or_eof, run_parser, uint, IResult};
+use aoc_2022::solver;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while},
-    character::complete::{alpha1, char, digit1, line_ending},
-    combinator::{all_consuming, eof, map, map_res, value},
-    error::convert_error,
+    character::complete::{alpha1, char, line_ending},
+    combinator::{eof, map, value},
     multi::many0,
     sequence::{separated_pair, terminated, tuple},
-    Finish,
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -24,39 +25,33 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-type IError<'a> = nom::error::VerboseError<&'a str>;
-type IResult<'a, T> = nom::IResult<&'a str, T, IError<'a>>;
-
-fn dir_entry(input: &str) -> IResult<Option<usize>> {
+fn dir_entry(input: &str) -> IResult<'_, Option<usize>> {
     alt((
-        map_res(
+        map(
             separated_pair(
-                digit1::<_, IError>,
+                uint,
                 char(' '),
                 take_while(|c: char| c == '.' || c.is_alphanumeric()),
             ),
-            |(s, _)| Ok::<_, anyhow::Error>(Some(s.parse()?)),
+            |(s, _)| Some(s),
         ),
         value(None, tuple((tag("dir "), alpha1))),
     ))(input)
 }
 
-fn line_end_or_eof(input: &str) -> IResult<&str> {
-    alt((line_ending, eof))(input)
-}
-
-fn list_dir(input: &str) -> IResult<usize> {
+// Sizes are added up as they're parsed, so an overflow is the parsed value rather than a parse error
+fn list_dir(input: &str) -> IResult<'_, Result<usize, Overflow>> {
     map(
         tuple((
             tag("$ ls"),
             line_end_or_eof,
             many0(tuple((dir_entry, line_end_or_eof))),
         )),
-        |(_, _, e)| e.iter().filter_map(|(s, _)| s.as_ref()).sum(),
+        |(_, _, e)| checked::sum(solver!(), 0, e.iter().filter_map(|(s, _)| *s)),
     )(input)
 }
 
-fn visit_dir(input: &str) -> IResult<Vec<usize>> {
+fn visit_dir(input: &str) -> IResult<'_, Result<Vec<usize>, Overflow>> {
     map(
         tuple((
             tuple((tag("$ cd "), alt((alpha1, tag("/"))), line_ending)),
@@ -69,30 +64,38 @@ fn visit_dir(input: &str) -> IResult<Vec<usize>> {
 }
 
 fn parse_session(input: &str) -> Result<Vec<usize>> {
-    let (_, ret) = all_consuming(visit_dir)(input)
-        .finish()
-        .map_err(|e| anyhow!("{}", convert_error(input, e)))?;
-    Ok(ret)
+    Ok(run_parser(visit_dir, input)??)
 }
 
-fn total_dir(size: usize, visits: Vec<Vec<usize>>) -> Vec<usize> {
-    let total = size + visits.iter().map(|v| v.last().unwrap_or(&0)).sum::<usize>();
+fn total_dir(
+    size: Result<usize, Overflow>,
+    visits: Vec<Result<Vec<usize>, Overflow>>,
+) -> Result<Vec<usize>, Overflow> {
+    let visits = visits.into_iter().collect::<Result<Vec<_>, _>>()?;
+    let below = visits.iter().map(|v| v.last().copied().unwrap_or(0));
+    let total = checked::sum(solver!(), size?, below)?;
     let mut dirs: Vec<_> = visits.into_iter().flatten().collect();
     dirs.push(total);
-    dirs
+    Ok(dirs)
 }
 
 fn part1(input: &str) -> Result<usize> {
     let dirs = parse_session(input)?;
-    Ok(dirs.iter().filter(|t| t <= &&100000).sum())
+    let small = dirs.iter().copied().filter(|&t| t <= 100000);
+    Ok(checked::sum(solver!(), 0, small)?)
 }
 
 fn part2(input: &str) -> Result<usize> {
     let dirs = parse_session(input)?;
     let (disk_size, req_size) = (70000000, 30000000);
-    let to_free = (dirs.last().unwrap() + req_size).saturating_sub(disk_size);
+    let used = dirs.last().context("No directories")?;
+    let to_free = checked::add(solver!(), *used, req_size)?.saturating_sub(disk_size);
     ensure!(to_free > 0, "No need to free");
-    Ok(*dirs.iter().filter(|s| s > &&to_free).min().unwrap())
+    dirs.iter()
+        .copied()
+        .filter(|&s| s > to_free)
+        .min()
+        .context("No directory is big enough")
 }
 
 fn solve(input: &str) -> Result<Answer> {
@@ -130,17 +133,11 @@ mod tests {
         );
     }
 
-    fn run_parser<'a, T>(parser: fn(&'a str) -> IResult<T>, input: &'a str) -> T {
-        parser(input)
-            .finish()
-            .map(|(left, v)| {
-                assert_eq!(left, "");
-                v
-            })
+    fn try_parser<'a, T>(parser: fn(&'a str) -> IResult<'a, T>, input: &'a str) -> T {
+        run_parser(parser, input)
             .map_err(|e| {
-                let msg = convert_error(input, e);
-                eprintln!("{msg}");
-                msg
+                eprintln!("{e}");
+                e
             })
             .unwrap()
     }
@@ -156,27 +153,36 @@ dir y
 100 c.txt
 dir z
 ";
-        assert_eq!(run_parser(list_dir, input), 111, "{input}");
+        assert_eq!(try_parser(list_dir, input), Ok(111), "{input}");
 
         let input = "$ ls
 ";
-        assert_eq!(run_parser(list_dir, input), 0, "{input}");
+        assert_eq!(try_parser(list_dir, input), Ok(0), "{input}");
 
         let input = "$ ls";
-        assert_eq!(run_parser(list_dir, input), 0, "{input}");
+        assert_eq!(try_parser(list_dir, input), Ok(0), "{input}");
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let input = format!("$ cd /\n$ ls\n{} a\n1 b\n$ cd ..", usize::MAX);
+        let err = solve(&input).unwrap_err();
+        let want = format!("day07_single_pass: overflow in {} + 1 (usize)", usize::MAX);
+        assert_eq!(err.to_string(), want);
     }
 
     #[test]
     fn test_visit_simple() {
         let input = "$ cd foo
 $ ls";
-        assert_eq!(run_parser(visit_dir, input), vec![0], "{input}");
+        assert_eq!(try_parser(visit_dir, input), Ok(vec![0]), "{input}");
 
         let input = "$ cd foo
 $ ls
 999 a.txt
 dir hi";
-        assert_eq!(run_parser(visit_dir, input), vec![999], "{input}");
+        assert_eq!(try_parser(visit_dir, input), Ok(vec![999]), "{input}");
 
         let input = "$ cd foo
 $ ls
@@ -185,7 +191,7 @@ dir hi
 $ cd bar
 $ ls
 10 something";
-        assert_eq!(run_parser(visit_dir, input), vec![10, 1009], "{input}");
+        assert_eq!(try_parser(visit_dir, input), Ok(vec![10, 1009]), "{input}");
 
         let input = "$ cd top
 $ ls
@@ -202,9 +208,11 @@ $ cd midtwo
 $ ls
 2 something";
         assert_eq!(
-            run_parser(visit_dir, input),
-            vec![100, 110, 2, 119],
+            try_parser(visit_dir, input),
+            Ok(vec![100, 110, 2, 119]),
             "{input}"
         );
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day07.txt"), solve);
 }
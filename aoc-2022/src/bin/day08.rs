@@ -1,6 +1,11 @@
 use std::cmp::max;
 
 use anyhow::Result;
+use aoc_2022::grid;
+use aoc_2022::nom_util::{self, run_parser, IResult};
+use aoc_2022::render::{render_arg, Image, Palette};
+use aoc_2022::trace::trace_arg;
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -9,18 +14,23 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day08.txt");
     println!("{:#?}", solve(d)?);
+    if let Some(path) = render_arg() {
+        render(d)?.save(&path)?;
+    }
     Ok(())
 }
 
 type Grid = Vec<Vec<i8>>;
 
-fn trees(input: &str) -> Grid {
-    input
-        .lines()
-        .map(|l| l.as_bytes().iter().map(|c| (c - b'0') as i8).collect())
-        .collect()
+fn tree_grid(input: &str) -> IResult<'_, grid::Grid<i8>> {
+    nom_util::grid(|c| c.to_digit(10).map(|d| d as i8))(input)
+}
+
+fn trees(input: &str) -> Result<Grid> {
+    Ok(run_parser(tree_grid, input)?.into_rows())
 }
 
 fn vis_corner(
@@ -41,27 +51,46 @@ fn vis_corner(
     }
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let trees = trees(input);
+fn visibility(trees: &Grid) -> Vec<Vec<bool>> {
     let num_rows = trees.len();
     let num_cols = trees[0].len();
 
     let mut vis: Vec<_> = trees.iter().map(|r| vec![false; r.len()]).collect();
-    vis_corner(&trees, &mut vis, 0..num_rows, 0..num_cols);
-    vis_corner(&trees, &mut vis, (0..num_rows).rev(), (0..num_cols).rev());
+    vis_corner(trees, &mut vis, 0..num_rows, 0..num_cols);
+    vis_corner(trees, &mut vis, (0..num_rows).rev(), (0..num_cols).rev());
+    vis
+}
+
+#[instrument(skip_all)]
+fn part1(input: &str) -> Result<usize> {
+    let vis = visibility(&trees(input)?);
     Ok(vis.iter().map(|r| r.iter().filter(|v| **v).count()).sum())
 }
 
+// Visible trees in green, hidden ones in blue, brighter for taller trees
+fn render(input: &str) -> Result<Image> {
+    let trees = trees(input)?;
+    let vis = visibility(&trees);
+    let visible = Palette::gradient([20, 70, 20], [130, 255, 130], 10);
+    let hidden = Palette::gradient([15, 15, 40], [90, 90, 150], 10);
+    let img = Image::from_fn(trees[0].len(), trees.len(), |x, y| {
+        let palette = if vis[y][x] { &visible } else { &hidden };
+        palette.get(trees[y][x] as usize)
+    });
+    Ok(img.scaled(4))
+}
+
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
-    let trees = trees(input);
+    let trees = trees(input)?;
     let r_max = trees.len() - 1;
     let c_max = trees[0].len() - 1;
 
     let mut scores: Vec<_> = trees.iter().map(|r| vec![0_usize; r.len()]).collect();
 
-    let mut r_last: Vec<_> = (0..=c_max).map(|_| vec![0_usize; 10]).collect();
+    let mut r_last: Vec<_> = (0..=c_max).map(|_| [0_usize; 10]).collect();
     for r in 1..r_max {
-        let mut c_last = vec![0; 10];
+        let mut c_last = [0; 10];
 
         for (c, cur_last) in r_last.iter_mut().enumerate().take(c_max).skip(1) {
             let t = trees[r][c] as usize;
@@ -74,9 +103,9 @@ fn part2(input: &str) -> Result<usize> {
         }
     }
 
-    let mut r_last: Vec<_> = (0..=c_max).map(|_| vec![r_max; 10]).collect();
+    let mut r_last: Vec<_> = (0..=c_max).map(|_| [r_max; 10]).collect();
     for r in (1..r_max).rev() {
-        let mut c_last = vec![c_max; 10];
+        let mut c_last = [c_max; 10];
 
         for c in (1..r_max).rev() {
             let t = trees[r][c] as usize;
@@ -96,6 +125,7 @@ fn part2(input: &str) -> Result<usize> {
         .unwrap())
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -130,4 +160,17 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_render() {
+        let img = render(include_str!("../../data/example/day08.txt")).unwrap();
+        assert_eq!((img.width(), img.height()), (20, 20));
+        // Edge trees are always visible (green), the middle 3 isn't (blue)
+        let [_, g, b] = img.get(0, 0).unwrap();
+        assert!(g > b);
+        let [_, g, b] = img.get(9, 9).unwrap();
+        assert!(b > g);
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day08.txt"), solve);
 }
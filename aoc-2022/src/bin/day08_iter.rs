@@ -4,6 +4,7 @@ use std::{
 };
 
 use anyhow::Result;
+use aoc_2022::diag::Diagnostic;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -27,7 +28,7 @@ impl Grid {
     }
 
     fn width(&self) -> usize {
-        self.rows[0].len()
+        self.rows.first().map_or(0, Vec::len)
     }
 
     fn row_iter(&self, r: usize) -> impl DoubleEndedIterator<Item = (usize, u8)> + '_ {
@@ -35,7 +36,10 @@ impl Grid {
     }
 
     fn inner_row_iter(&self, r: usize) -> impl DoubleEndedIterator<Item = (usize, u8)> + '_ {
-        self.rows[r][1..self.width() - 1]
+        // Grids narrower than 3 have no inner trees
+        self.rows[r]
+            .get(1..self.width().saturating_sub(1))
+            .unwrap_or_default()
             .iter()
             .enumerate()
             .map(|(c, t)| (c + 1, *t))
@@ -49,7 +53,9 @@ impl Grid {
     }
 
     fn inner_col_iter(&self, c: usize) -> impl DoubleEndedIterator<Item = (usize, u8)> + '_ {
-        self.rows[1..self.height() - 1]
+        self.rows
+            .get(1..self.height().saturating_sub(1))
+            .unwrap_or_default()
             .iter()
             .enumerate()
             .map(move |(r, row)| (r + 1, row[c]))
@@ -60,10 +66,28 @@ impl FromStr for Grid {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let rows = s
+        let rows: Vec<Vec<u8>> = s
             .lines()
-            .map(|l| l.as_bytes().iter().map(|c| c - b'0').collect())
-            .collect();
+            .map(|l| {
+                l.char_indices()
+                    .map(|(i, c)| match c.to_digit(10) {
+                        Some(d) => Ok(d as u8),
+                        None => {
+                            let msg = format!("{c:?} isn't a digit");
+                            Err(Diagnostic::new(s, &l[i..i + c.len_utf8()], msg))
+                        }
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(Diagnostic::at(s, 0, 0, "empty grid").into());
+        }
+        if let Some((l, _)) = s.lines().zip(&rows).find(|(_, r)| r.len() != width) {
+            let msg = format!("{} long, the first row is {width}", l.chars().count());
+            return Err(Diagnostic::new(s, l, msg).into());
+        }
         Ok(Self { rows })
     }
 }
@@ -244,4 +268,15 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_error_location() {
+        for (input, want) in [("12\n3x\n", (2, 2)), ("12\n3\n", (2, 1)), ("", (1, 1))] {
+            let err = solve(input).unwrap_err();
+            let d = err.downcast_ref::<Diagnostic>().unwrap();
+            assert_eq!((d.line, d.column), want, "{d}");
+        }
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day08.txt"), solve);
 }
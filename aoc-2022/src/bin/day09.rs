@@ -1,6 +1,12 @@
-use std::{collections::HashSet, str::FromStr};
+use std::{collections::HashSet, ops::RangeInclusive, rc::Rc, str::FromStr};
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::Result;
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{
+    anim::{self, Frame},
+    diag::{parse_lines, parse_span, Diagnostic},
+};
+use tracing::instrument;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Answer {
@@ -9,8 +15,10 @@ struct Answer {
 }
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day09.txt");
     println!("{:#?}", solve(d)?);
+    anim::from_args(|| frames(d))?;
     Ok(())
 }
 
@@ -23,15 +31,15 @@ enum Dir {
 }
 
 impl FromStr for Dir {
-    type Err = anyhow::Error;
+    type Err = Diagnostic;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
         let d = match s {
             "D" => Self::Down,
             "U" => Self::Up,
             "L" => Self::Left,
             "R" => Self::Right,
-            _ => bail!("Unrecognized direction {s}"),
+            _ => return Err(Diagnostic::new(s, s, format!("Unrecognized direction {s}"))),
         };
         Ok(d)
     }
@@ -44,13 +52,15 @@ struct Move {
 }
 
 impl FromStr for Move {
-    type Err = anyhow::Error;
+    type Err = Diagnostic;
 
-    fn from_str(s: &str) -> Result<Self> {
-        let (dir, amount) = s.split_once(' ').ok_or_else(|| anyhow!("No space"))?;
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
+        let (dir, amount) = s
+            .split_once(' ')
+            .ok_or_else(|| Diagnostic::new(s, s, "No space"))?;
         Ok(Self {
-            dir: dir.parse()?,
-            amount: amount.parse()?,
+            dir: dir.parse().map_err(|d: Diagnostic| d.within(s, dir))?,
+            amount: parse_span(s, amount)?,
         })
     }
 }
@@ -113,10 +123,7 @@ where
     F: FnMut(&Knot) -> Result<()>,
 {
     // Parse input up-front so visit isn't called if there's a parse error
-    let moves = input
-        .lines()
-        .map(|l| l.parse())
-        .collect::<Result<Vec<Move>, _>>()?;
+    let moves: Vec<Move> = parse_lines(input)?;
 
     let mut k = Knot::default();
     visit(&k)?;
@@ -131,6 +138,7 @@ where
     Ok(())
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<usize> {
     let mut seen = HashSet::new();
     let mut tail = Knot::default();
@@ -142,6 +150,7 @@ fn part1(input: &str) -> Result<usize> {
     Ok(seen.len())
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     let mut seen = HashSet::new();
     let mut tails = [Knot::default(); 9];
@@ -157,6 +166,81 @@ fn part2(input: &str) -> Result<usize> {
     Ok(seen.len())
 }
 
+// The 10 knot rope after one step of the head, with the path the tail has taken so far
+struct RopeFrame {
+    knots: [Knot; 10],
+    trail: Rc<Vec<Knot>>,
+    steps: usize,
+    xs: RangeInclusive<i32>,
+    ys: RangeInclusive<i32>,
+}
+
+impl Frame for RopeFrame {
+    fn draw(&self) -> String {
+        // Big inputs wander far, so follow the head rather than showing everything
+        const W: i32 = 80;
+        const H: i32 = 30;
+        let view = |r: &RangeInclusive<i32>, at: i32, size: i32| {
+            if r.end() - r.start() < size {
+                r.clone()
+            } else {
+                at - size / 2..=at + size / 2 - 1
+            }
+        };
+        let xs = view(&self.xs, self.knots[0].x, W);
+        let ys = view(&self.ys, self.knots[0].y, H);
+
+        let trail: HashSet<_> = self.trail[..self.steps].iter().collect();
+        let mut out = String::new();
+        for y in ys.rev() {
+            for x in xs.clone() {
+                let k = Knot { x, y };
+                out.push(match self.knots.iter().position(|&n| n == k) {
+                    Some(0) => 'H',
+                    Some(i) => char::from_digit(i as u32, 10).unwrap(),
+                    None if k == Knot::default() => 's',
+                    None if trail.contains(&k) => '#',
+                    None => '.',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn frames(input: &str) -> Result<Vec<RopeFrame>> {
+    let mut steps = Vec::new();
+    let mut knots = [Knot::default(); 10];
+    simulate(input, |head| {
+        knots[0] = *head;
+        for i in 1..knots.len() {
+            let lead = knots[i - 1];
+            knots[i].follow(&lead)?;
+        }
+        steps.push(knots);
+        Ok(())
+    })?;
+
+    let all = || steps.iter().flatten();
+    let xs = all().map(|k| k.x).min().unwrap_or(0)..=all().map(|k| k.x).max().unwrap_or(0);
+    let ys = all().map(|k| k.y).min().unwrap_or(0)..=all().map(|k| k.y).max().unwrap_or(0);
+    let trail = Rc::new(steps.iter().map(|s| s[9]).collect::<Vec<_>>());
+    let frames = steps
+        .iter()
+        .enumerate()
+        .map(|(i, &knots)| RopeFrame {
+            knots,
+            trail: trail.clone(),
+            steps: i + 1,
+            xs: xs.clone(),
+            ys: ys.clone(),
+        })
+        .collect();
+    Ok(frames)
+}
+
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<Answer> {
     let part1 = part1(input)?;
     let part2 = part2(input)?;
@@ -186,6 +270,19 @@ mod tests {
         assert_eq!(p2, 36);
     }
 
+    #[test]
+    fn test_frames() {
+        let frames = frames(include_str!("../../data/example/day09.txt")).unwrap();
+        // The starting position, then one frame per step of the head
+        assert_eq!(frames.len(), 25);
+        assert_eq!(frames[0].draw(), "......\n......\n......\n......\nH.....\n");
+        assert_eq!(frames[4].draw(), "......\n......\n......\n......\n4321H.\n");
+        assert_eq!(
+            frames[24].draw(),
+            "......\n......\n.1H3..\n.5....\n6.....\n"
+        );
+    }
+
     #[test]
     fn challenge() {
         let answer = solve(include_str!("../../data/challenge/day09.txt")).unwrap();
@@ -197,4 +294,6 @@ mod tests {
             }
         );
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day09.txt"), solve);
 }
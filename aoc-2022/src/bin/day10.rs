@@ -1,12 +1,22 @@
-use std::str::FromStr;
-
-use anyhow::{anyhow, ensure, Context, Ok, Result};
+use std::{rc::Rc, str::FromStr};
+
+use anyhow::{Context, Result};
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{
+    anim::{self, Frame},
+    checked::{self, Overflow},
+    diag::{parse_lines, parse_span, Diagnostic},
+    solver,
+};
+use tracing::instrument;
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     let d = include_str!("../../data/challenge/day10.txt");
     let s = solve(d)?;
     println!("{}", s.0);
     println!("{}", s.1);
+    anim::from_args(|| frames(d))?;
     Ok(())
 }
 
@@ -29,54 +39,57 @@ impl IntoIterator for Inst {
 }
 
 impl FromStr for Inst {
-    type Err = anyhow::Error;
+    type Err = Diagnostic;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, Diagnostic> {
         match s {
             "noop" => Ok(Self::Noop),
             _ => {
-                let (name, num) = s.split_once(' ').ok_or_else(|| anyhow!("No space"))?;
-                ensure!(name == "addx", "Unexpected first part {name:?}");
-                let i = num.parse().context("Parsing addx")?;
-                Ok(Self::Addx(i))
+                let (name, num) = s
+                    .split_once(' ')
+                    .ok_or_else(|| Diagnostic::new(s, s, "No space"))?;
+                if name != "addx" {
+                    let msg = format!("Unexpected first part {name:?}");
+                    return Err(Diagnostic::new(s, name, msg));
+                }
+                Ok(Self::Addx(parse_span(s, num)?))
             }
         }
     }
 }
 
 fn parse_input(input: &str) -> Result<Vec<Inst>> {
-    input
-        .lines()
-        .enumerate()
-        .map(|(i, l)| l.parse::<Inst>().with_context(|| format!("Line {i} {l:?}")))
-        .collect()
+    Ok(parse_lines(input)?)
 }
 
-fn cycles(inst: Vec<Inst>) -> impl Iterator<Item = (i32, i32)> {
-    inst.into_iter()
-        .flatten()
-        .enumerate()
-        .scan(1_i32, |state, (pc, delta)| {
-            let prev = *state;
-            let pc = (pc + 1) as i32;
-            *state += delta as i32;
-            Some((pc, prev))
-        })
+// Each cycle with X during it
+fn cycles(inst: Vec<Inst>) -> Result<Vec<(i32, i32)>, Overflow> {
+    let mut x = 1_i32;
+    let mut out = Vec::new();
+    for (pc, delta) in inst.into_iter().flatten().enumerate() {
+        out.push(((pc + 1) as i32, x));
+        x = checked::add(solver!(), x, delta as i32)?;
+    }
+    Ok(out)
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<i32> {
-    let s = cycles(parse_input(input)?).fold(0, |score, (pc, val)| {
-        score
-            + match pc {
-                20 | 60 | 100 | 140 | 180 | 220 => val * pc,
-                _ => 0,
+    let s = cycles(parse_input(input)?)?
+        .into_iter()
+        .try_fold(0, |score, (pc, val)| match pc {
+            20 | 60 | 100 | 140 | 180 | 220 => {
+                checked::add(solver!(), score, checked::mul(solver!(), val, pc)?)
             }
-    });
+            _ => Ok(score),
+        })?;
     Ok(s)
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<String> {
-    let s = cycles(parse_input(input)?)
+    let s = cycles(parse_input(input)?)?
+        .into_iter()
         .flat_map(|(pc, val)| {
             let y = (pc - 1) % 40;
             let ch = if y.abs_diff(val) < 2 { "#" } else { "." };
@@ -87,6 +100,52 @@ fn part2(input: &str) -> Result<String> {
     Ok(s)
 }
 
+// The CRT part way through drawing: the sprite, then the pixels drawn up to this cycle
+struct CrtFrame {
+    pixels: Rc<Vec<bool>>,
+    cycle: usize,
+    x: i32,
+}
+
+impl Frame for CrtFrame {
+    fn draw(&self) -> String {
+        let mut out = format!("cycle {:>3}  X = {}\n", self.cycle, self.x);
+        out.extend((0..40).map(|c: i32| if c.abs_diff(self.x) < 2 { '#' } else { '.' }));
+        out += "\n\n";
+        for (i, lit) in self.pixels.iter().enumerate() {
+            out.push(match i + 1 {
+                pc if pc > self.cycle => ' ',
+                _ if *lit => '#',
+                _ => '.',
+            });
+            if i % 40 == 39 {
+                out.push('\n')
+            }
+        }
+        out
+    }
+}
+
+fn frames(input: &str) -> Result<Vec<CrtFrame>> {
+    let cycles = cycles(parse_input(input)?)?;
+    let pixels = Rc::new(
+        cycles
+            .iter()
+            .map(|(pc, val)| ((pc - 1) % 40).abs_diff(*val) < 2)
+            .collect::<Vec<_>>(),
+    );
+    let frames = cycles
+        .iter()
+        .map(|&(pc, x)| CrtFrame {
+            pixels: pixels.clone(),
+            cycle: pc as usize,
+            x,
+        })
+        .collect();
+    Ok(frames)
+}
+
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<(i32, String)> {
     let part1 = part1(input).context("part 1")?;
     let part2 = part2(input).context("part 2")?;
@@ -117,6 +176,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_frames() {
+        let frames = frames(include_str!("../../data/example/day10.txt")).unwrap();
+        assert_eq!(frames.len(), 240);
+        let third = frames[2].draw();
+        let mut lines = third.lines();
+        assert_eq!(lines.next(), Some("cycle   3  X = 16"));
+        assert_eq!(
+            lines.next(),
+            Some(format!("{}###{}", ".".repeat(15), ".".repeat(22)).as_str())
+        );
+        assert_eq!(lines.nth(1).map(str::trim_end), Some("##."));
+
+        // The last frame is the finished picture
+        let last = frames[239].draw();
+        assert!(last.ends_with(&part2(include_str!("../../data/example/day10.txt")).unwrap()));
+    }
+
     #[test]
     fn challenge() {
         let answer = solve(include_str!("../../data/challenge/day10.txt")).unwrap();
@@ -135,4 +212,6 @@ mod tests {
             )
         );
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day10.txt"), solve);
 }
@@ -1,18 +1,25 @@
-#![feature(type_alias_impl_trait)]
-
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
+use aoc_2022::budget;
+use aoc_2022::nom_util::{blocks, keyed, run_parser, uint, IResult};
+use aoc_2022::num::checked_lcm_all;
+use aoc_2022::stats::top_k;
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{
+    checked::{self, Overflow},
+    par, solver,
+};
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{char, digit1, line_ending},
-    combinator::{all_consuming, map, map_res},
-    error::convert_error,
+    combinator::{map, map_res, value},
     multi::separated_list0,
-    sequence::{pair, preceded, separated_pair, terminated, tuple},
-    Finish,
+    sequence::{preceded, separated_pair, terminated, tuple},
 };
+use tracing::instrument;
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     for s in [
         include_str!("../../data/example/day11.txt"),
         include_str!("../../data/challenge/day11.txt"),
@@ -44,10 +51,10 @@ enum Op {
 }
 
 impl Op {
-    fn apply(&self, v: u64) -> u64 {
+    fn apply(&self, v: u64) -> Result<u64, Overflow> {
         match self {
-            Op::Add(rhs) => v + rhs.val(v),
-            Op::Mult(rhs) => v * rhs.val(v),
+            Op::Add(rhs) => checked::add(solver!(), v, rhs.val(v)),
+            Op::Mult(rhs) => checked::mul(solver!(), v, rhs.val(v)),
         }
     }
 }
@@ -63,19 +70,19 @@ struct Monkey {
 }
 
 impl Monkey {
-    fn inspect(&mut self, adjust: impl Fn(u64) -> u64) -> Vec<(usize, u64)> {
-        self.inspected += self.items.len();
+    fn inspect(&mut self, adjust: impl Fn(u64) -> u64) -> Result<Vec<(usize, u64)>, Overflow> {
+        self.inspected = checked::add(solver!(), self.inspected, self.items.len())?;
         self.items
             .drain(..)
             .map(|v| {
-                let mut new = self.op.apply(v);
+                let mut new = self.op.apply(v)?;
                 new = adjust(new);
-                let dest = if new % self.fact == 0 {
+                let dest = if new.is_multiple_of(self.fact) {
                     self.t_dest
                 } else {
                     self.f_dest
                 };
-                (dest, new)
+                Ok((dest, new))
             })
             .collect()
     }
@@ -87,37 +94,46 @@ struct MonkeySim {
 }
 
 impl MonkeySim {
-    fn run_once(&mut self, adjust: impl Fn(u64) -> u64) {
+    fn new(monkeys: Vec<Monkey>) -> Result<Self> {
+        for (i, m) in monkeys.iter().enumerate() {
+            for dest in [m.t_dest, m.f_dest] {
+                ensure!(
+                    dest < monkeys.len(),
+                    "Monkey {i} throws to monkey {dest}, but there are only {}",
+                    monkeys.len()
+                );
+            }
+        }
+        Ok(Self { monkeys })
+    }
+
+    #[instrument(skip_all)]
+    fn run_once(&mut self, adjust: impl Fn(u64) -> u64) -> Result<(), Overflow> {
         for i in 0..self.monkeys.len() {
-            for (dest, val) in self.monkeys[i].inspect(&adjust) {
+            for (dest, val) in self.monkeys[i].inspect(&adjust)? {
                 self.monkeys[dest].items.push(val)
             }
         }
+        Ok(())
     }
 }
 
-type IError<'a> = nom::error::VerboseError<&'a str>;
-type IResult<'a, T> = nom::IResult<&'a str, T, IError<'a>>;
-
-fn starting_items(input: &str) -> IResult<Vec<u64>> {
-    preceded(
-        tag("  Starting items: "),
-        separated_list0(tag(", "), map_res(digit1, str::parse)),
-    )(input)
+fn starting_items(input: &str) -> IResult<'_, Vec<u64>> {
+    keyed("Starting items", separated_list0(tag(", "), uint))(input)
 }
 
-fn rhs(input: &str) -> IResult<Rhs> {
-    map_res::<_, _, _, _, anyhow::Error, _, _>(alt((tag("old"), digit1)), |v| match v {
-        "old" => Ok(Rhs::Old),
-        num => Ok(Rhs::Const(num.parse().context("rhs")?)),
-    })(input)
+fn rhs(input: &str) -> IResult<'_, Rhs> {
+    alt((value(Rhs::Old, tag("old")), map(uint, Rhs::Const)))(input)
 }
 
-fn operation(input: &str) -> IResult<Op> {
+fn operation(input: &str) -> IResult<'_, Op> {
     map(
-        preceded(
-            tag("  Operation: new = old "),
-            separated_pair(alt((char('*'), char('+'))), char(' '), rhs),
+        keyed(
+            "Operation",
+            preceded(
+                tag("new = old "),
+                separated_pair(alt((char('*'), char('+'))), char(' '), rhs),
+            ),
         ),
         |(op, rhs)| match op {
             '*' => Op::Mult(rhs),
@@ -127,26 +143,21 @@ fn operation(input: &str) -> IResult<Op> {
     )(input)
 }
 
-fn test_clause(input: &str) -> IResult<(u64, usize, usize)> {
-    map_res::<_, _, _, _, anyhow::Error, _, _>(
-        tuple((
-            preceded(tag("  Test: divisible by "), digit1::<&str, _>),
+fn test_clause(input: &str) -> IResult<'_, (u64, usize, usize)> {
+    tuple((
+        terminated(
+            keyed("Test", preceded(tag("divisible by "), uint)),
             line_ending,
-            preceded(tag("    If true: throw to monkey "), digit1),
+        ),
+        terminated(
+            keyed("If true", preceded(tag("throw to monkey "), uint)),
             line_ending,
-            preceded(tag("    If false: throw to monkey "), digit1),
-        )),
-        |(fact, _, t, _, f)| {
-            Ok((
-                fact.parse().context("divisor")?,
-                t.parse().context("true dest")?,
-                f.parse().context("false dest")?,
-            ))
-        },
-    )(input)
+        ),
+        keyed("If false", preceded(tag("throw to monkey "), uint)),
+    ))(input)
 }
 
-fn monkey(input: &str) -> IResult<Monkey> {
+fn monkey(input: &str) -> IResult<'_, Monkey> {
     map(
         tuple((
             tuple((tag("Monkey "), digit1, char(':'), line_ending)),
@@ -165,51 +176,62 @@ fn monkey(input: &str) -> IResult<Monkey> {
     )(input)
 }
 
-fn monkey_sim(input: &str) -> IResult<MonkeySim> {
-    map(
-        separated_list0(pair(line_ending, line_ending), monkey),
-        |monkeys| MonkeySim { monkeys },
-    )(input)
-}
-
-fn run_parser<'a, T>(parser: fn(&'a str) -> IResult<T>, input: &'a str) -> Result<T> {
-    all_consuming(parser)(input)
-        .finish()
-        .map(|(_, out)| out)
-        .map_err(|e| {
-            let msg = convert_error(input, e);
-            anyhow!("Parse error: {msg}")
-        })
+fn monkey_sim(input: &str) -> IResult<'_, MonkeySim> {
+    map_res(blocks(monkey), MonkeySim::new)(input)
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<usize> {
     let mut sim = run_parser(monkey_sim, input)?;
+    let cancel = budget!()?;
     for _ in 0..20 {
-        sim.run_once(|i| i / 3);
+        cancel.check()?;
+        sim.run_once(|i| i / 3)?;
     }
-    let mut business: Vec<_> = sim.monkeys.into_iter().map(|m| m.inspected).collect();
-    business.sort();
-    let [.., m, n] = &business[..] else { bail!("Too few monkeys") };
+    monkey_business(&sim)
+}
 
-    Ok(m * n)
+// Every monkey's test still gives the same answer when worry levels are reduced modulo this
+fn modulus(sim: &MonkeySim) -> Result<u64> {
+    let facts = sim.monkeys.iter().map(|m| m.fact);
+    ensure!(
+        facts.clone().all(|f| f > 0),
+        "A monkey tests divisibility by 0"
+    );
+    checked_lcm_all(facts.clone()).with_context(|| {
+        let facts: Vec<_> = facts.collect();
+        format!("LCM of divisors {facts:?} overflows u64")
+    })
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     let mut sim = run_parser(monkey_sim, input)?;
     // use modular monkeys in simian simulation
-    let lcm: u64 = sim.monkeys.iter().map(|m| m.fact).product();
+    let lcm = modulus(&sim)?;
+    let cancel = budget!()?;
     for _ in 1..=10000 {
-        sim.run_once(|x| x % lcm);
+        cancel.check()?;
+        sim.run_once(|x| x % lcm)?;
     }
-    let mut business: Vec<_> = sim.monkeys.into_iter().map(|m| m.inspected).collect();
-    business.sort();
-    let [.., m, n] = &business[..] else { bail!("Too few monkeys") };
-    Ok(m * n)
+    monkey_business(&sim)
+}
+
+// Product of the two highest inspection counts
+fn monkey_business(sim: &MonkeySim) -> Result<usize> {
+    let top = top_k(sim.monkeys.iter().map(|m| m.inspected), 2);
+    let [(_, m), (_, n)] = top[..] else {
+        bail!("Too few monkeys")
+    };
+    Ok(checked::mul(solver!(), m, n)?)
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<(usize, usize)> {
-    let part1 = part1(input).context("part 1")?;
-    let part2 = part2(input).context("part 2")?;
+    // Each part parses its own monkeys, so they're independent
+    let (part1, part2) = par::join(|| part1(input), || part2(input));
+    let part1 = part1.context("part 1")?;
+    let part2 = part2.context("part 2")?;
 
     Ok((part1, part2))
 }
@@ -230,7 +252,43 @@ mod tests {
         assert_eq!(answer, (54054, 14314925001));
     }
 
-    fn try_parser<'a, T>(parser: fn(&'a str) -> IResult<T>, input: &'a str) -> T {
+    #[test]
+    fn test_modulus() {
+        let sim = |facts: &[u64]| MonkeySim {
+            monkeys: facts
+                .iter()
+                .map(|&fact| Monkey {
+                    items: vec![],
+                    op: Op::Add(Rhs::Old),
+                    fact,
+                    t_dest: 0,
+                    f_dest: 0,
+                    inspected: 0,
+                })
+                .collect(),
+        };
+        assert_eq!(modulus(&sim(&[2, 3, 5])).unwrap(), 30);
+        // The product would be 96
+        assert_eq!(modulus(&sim(&[4, 6, 4])).unwrap(), 12);
+        // Two primes just under 2^32, so only their product is a common multiple
+        let err = modulus(&sim(&[4294967291, 4294967279, 2])).unwrap_err();
+        assert!(err.to_string().contains("overflows u64"), "{err}");
+        assert!(modulus(&sim(&[2, 0])).is_err());
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let op = Op::Mult(Rhs::Old);
+        assert_eq!(op.apply(1 << 31), Ok(1 << 62));
+        let err = op.apply(1 << 32).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day11: overflow in 4294967296 * 4294967296 (u64)"
+        );
+    }
+
+    fn try_parser<'a, T>(parser: fn(&'a str) -> IResult<'a, T>, input: &'a str) -> T {
         run_parser(parser, input)
             .with_context(|| format!("parsing: {input:?}"))
             .unwrap()
@@ -306,4 +364,6 @@ mod tests {
             assert_eq!(try_parser(monkey, input), want, "input: {input:?}")
         }
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day11.txt"), solve);
 }
@@ -1,17 +1,35 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     str::FromStr,
 };
 
 use anyhow::{anyhow, Context, Result};
+use aoc_2022::trace::trace_arg;
+use aoc_2022::{
+    budget,
+    checked::{self, Overflow},
+    diag::Diagnostic,
+    lint::{lint_arg, Assumption},
+    par,
+    render::{render_arg, Image, Palette, RED},
+    solver,
+};
+use tracing::instrument;
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
+    if lint_arg(&ASSUMPTIONS)? {
+        return Ok(());
+    }
     for s in [
         include_str!("../../data/example/day12.txt"),
         include_str!("../../data/challenge/day12.txt"),
     ] {
         eprintln!("{:#?}", solve(s)?)
     }
+    if let Some(path) = render_arg() {
+        render(include_str!("../../data/challenge/day12.txt"))?.save(&path)?;
+    }
     Ok(())
 }
 
@@ -21,6 +39,10 @@ struct Grid {
 }
 
 impl Grid {
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
     fn get(&self, x: isize, y: isize) -> Option<u8> {
         if x < 0 || y < 0 {
             return None;
@@ -53,13 +75,87 @@ impl FromStr for Grid {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let rows = s.lines().map(|l| l.as_bytes().to_vec()).collect();
+        let rows: Vec<Vec<u8>> = s.lines().map(|l| l.as_bytes().to_vec()).collect();
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some((l, _)) = s.lines().zip(&rows).find(|(_, r)| r.len() != width) {
+            let msg = format!("{} long, the first row is {width}", l.len());
+            return Err(Diagnostic::new(s, l, msg).into());
+        }
         Ok(Self { rows })
     }
 }
 
 type Coord = (isize, isize);
 
+// Checked by `aoc lint`, see src/lint.rs
+const ASSUMPTIONS: [Assumption; 4] = [
+    Assumption {
+        what: "there's exactly one S and one E",
+        check: one_start_and_end,
+    },
+    Assumption {
+        what: "rows are all the same length",
+        check: same_length_rows,
+    },
+    Assumption {
+        what: "heights are a to z, apart from S and E",
+        check: heights,
+    },
+    Assumption {
+        what: "no row has more than one a, as part 2 only starts from the first in each row",
+        check: one_a_per_row,
+    },
+];
+
+fn one_start_and_end(input: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for needle in ["S", "E"] {
+        let mut at = input.match_indices(needle);
+        if at.next().is_none() {
+            found.push(Diagnostic::at(input, 0, 0, format!("no {needle}")));
+        }
+        for (i, _) in at {
+            found.push(Diagnostic::at(input, i, 1, format!("another {needle}")));
+        }
+    }
+    found
+}
+
+fn same_length_rows(input: &str) -> Vec<Diagnostic> {
+    let mut lines = input.lines();
+    let Some(first) = lines.next() else {
+        return vec![];
+    };
+    lines
+        .filter(|l| l.len() != first.len())
+        .map(|l| {
+            let msg = format!("{} long, the first row is {}", l.len(), first.len());
+            Diagnostic::new(input, l, msg)
+        })
+        .collect()
+}
+
+fn heights(input: &str) -> Vec<Diagnostic> {
+    input
+        .lines()
+        .flat_map(|l| l.char_indices().map(move |(i, c)| (l, i, c)))
+        .filter(|(_, _, c)| !c.is_ascii_lowercase() && *c != 'S' && *c != 'E')
+        .map(|(l, i, c)| Diagnostic::new(input, &l[i..i + c.len_utf8()], format!("{c:?}")))
+        .collect()
+}
+
+fn one_a_per_row(input: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for l in input.lines() {
+        let mut a = l.match_indices('a').skip(1);
+        if let Some((i, _)) = a.next() {
+            let msg = format!("{} more a in this row won't be tried", a.count() + 1);
+            found.push(Diagnostic::new(input, &l[i..i + 1], msg));
+        }
+    }
+    found
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct Graph {
     start: Coord,
@@ -67,16 +163,17 @@ struct Graph {
     grid: Grid,
 }
 
-fn reach(from: u8, to: u8) -> bool {
+fn reach(from: u8, to: u8) -> Result<bool, Overflow> {
     // We obviously want the start to reach anything, and anything to reach the end.
     // Additionally, nothing can reach start and the end can reach nothing.
-    match (from, to) {
+    let r = match (from, to) {
         (b'S', _) => true,
         (b'E', _) => false,
-        (_, b'E') => b'z' <= (from + 1),
+        (_, b'E') => b'z' <= checked::add(solver!(), from, 1)?,
         (_, b'S') => false,
-        (from, to) => to <= (from + 1),
-    }
+        (from, to) => to <= checked::add(solver!(), from, 1)?,
+    };
+    Ok(r)
 }
 
 impl Graph {
@@ -90,7 +187,7 @@ impl Graph {
         for (dx, dy) in [(0, 1), (0, -1), (-1, 0), (1, 0)] {
             let (tx, ty) = (x + dx, y + dy);
             if let Some(to) = self.grid.get(tx, ty) {
-                if reach(from, to) {
+                if reach(from, to)? {
                     ret.push((tx, ty));
                 }
             }
@@ -98,8 +195,9 @@ impl Graph {
         Ok(ret)
     }
 
+    #[instrument(skip(self))]
     fn shortest_path(&self, x: isize, y: isize) -> Result<Option<usize>> {
-        let mut seen = HashSet::with_capacity(self.grid.rows.len() * self.grid.rows[0].len());
+        let mut seen = HashSet::with_capacity(self.grid.rows.len() * self.grid.width());
         let mut q = VecDeque::with_capacity(self.grid.rows.len());
         q.push_back(((x, y), 0));
         while let Some(((x, y), dist)) = q.pop_front() {
@@ -119,6 +217,31 @@ impl Graph {
         }
         Ok(None)
     }
+
+    // Like shortest_path, but returns the cells along the way (including both ends)
+    fn route(&self, x: isize, y: isize) -> Result<Option<Vec<Coord>>> {
+        let mut prev = HashMap::new();
+        let mut q = VecDeque::new();
+        prev.insert((x, y), (x, y));
+        q.push_back((x, y));
+        while let Some(cur) = q.pop_front() {
+            if b'E' == self.grid.get(cur.0, cur.1).context("visiting")? {
+                let mut path = vec![cur];
+                while path[path.len() - 1] != (x, y) {
+                    path.push(prev[&path[path.len() - 1]]);
+                }
+                path.reverse();
+                return Ok(Some(path));
+            }
+            for dest in self.adj(cur.0, cur.1).context("traversing")? {
+                if let Entry::Vacant(e) = prev.entry(dest) {
+                    e.insert(cur);
+                    q.push_back(dest);
+                }
+            }
+        }
+        Ok(None)
+    }
 }
 
 impl TryFrom<Grid> for Graph {
@@ -131,23 +254,54 @@ impl TryFrom<Grid> for Graph {
     }
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<usize> {
     let g: Graph = input.parse::<Grid>()?.try_into()?;
     g.shortest_path(g.start.0, g.start.1)?
         .ok_or_else(|| anyhow!("No path found"))
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     let g: Graph = input.parse::<Grid>()?.try_into()?;
-    g.grid
-        .find_all(b'a')
+    // Each start is a separate BFS, so they can run at the same time
+    let cancel = budget!()?;
+    let dists = par::map(&g.grid.find_all(b'a'), |(x, y)| {
+        cancel.check()?;
+        g.shortest_path(*x, *y)
+    });
+    dists
         .into_iter()
-        // ew, unwrap
-        .filter_map(|(x, y)| g.shortest_path(x, y).unwrap())
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
         .min()
         .ok_or_else(|| anyhow!("No path found"))
 }
 
+// Heightmap from dark green (a) to white (z), with the part 1 path in red
+fn render(input: &str) -> Result<Image> {
+    let g: Graph = input.parse::<Grid>()?.try_into()?;
+    let path = g
+        .route(g.start.0, g.start.1)?
+        .ok_or_else(|| anyhow!("No path found"))?;
+    let heights = Palette::gradient([30, 60, 30], [240, 240, 240], 26);
+    let rows = &g.grid.rows;
+    let mut img = Image::from_fn(g.grid.width(), rows.len(), |col, row| {
+        let h = match rows[row][col] {
+            b'S' => b'a',
+            b'E' => b'z',
+            h => h,
+        };
+        heights.get(h.saturating_sub(b'a') as usize)
+    });
+    for (x, y) in path {
+        img.set(y as usize, x as usize, RED);
+    }
+    Ok(img.scaled(4))
+}
+
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<(usize, usize)> {
     let part1 = part1(input).context("part 1")?;
     let part2 = part2(input).context("part 2")?;
@@ -158,6 +312,7 @@ fn solve(input: &str) -> Result<(usize, usize)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022::lint::lint;
 
     #[test]
     fn example() {
@@ -184,6 +339,7 @@ mod tests {
                 "{s:?}"
             )
         }
+        assert!("ab\nc".parse::<Grid>().is_err());
     }
 
     #[test]
@@ -205,10 +361,17 @@ mod tests {
             (('x', 'z'), false),
             (('a', 'z'), false),
         ] {
-            assert_eq!(reach(from as u8, to as u8), want, "{from} -> {to}")
+            assert_eq!(reach(from as u8, to as u8), Ok(want), "{from} -> {to}")
         }
     }
 
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let err = reach(255, b'a').unwrap_err();
+        assert_eq!(err.to_string(), "day12: overflow in 255 + 1 (u8)");
+    }
+
     #[test]
     fn test_position() {
         let g = Grid {
@@ -250,4 +413,71 @@ mod tests {
             assert_eq!(got, want, "({x}, {y})")
         }
     }
+
+    #[test]
+    fn test_route() {
+        let g: Graph = include_str!("../../data/example/day12.txt")
+            .parse::<Grid>()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let path = g.route(g.start.0, g.start.1).unwrap().unwrap();
+        assert_eq!(path.len(), 32);
+        assert_eq!(path.first(), Some(&g.start));
+        assert_eq!(path.last(), Some(&g.end));
+        for w in path.windows(2) {
+            assert!(g.adj(w[0].0, w[0].1).unwrap().contains(&w[1]), "{w:?}");
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let img = render(include_str!("../../data/example/day12.txt")).unwrap();
+        assert_eq!((img.width(), img.height()), (32, 20));
+        assert_eq!(img.get(0, 0), Some(RED));
+    }
+
+    #[test]
+    fn test_assumptions() {
+        let broken = |input| -> Vec<(&str, usize)> {
+            lint(input, &ASSUMPTIONS)
+                .into_iter()
+                .map(|(a, found)| (a.what, found.len()))
+                .filter(|(_, n)| *n > 0)
+                .collect()
+        };
+        assert_eq!(broken(include_str!("../../data/example/day12.txt")), []);
+        // Every row has a in the first column and more later on. The answer is still right because
+        // the best start is in the first column.
+        assert_eq!(
+            broken(include_str!("../../data/challenge/day12.txt")),
+            [(ASSUMPTIONS[3].what, 41)]
+        );
+
+        let input = "SaaE\nab\nc?E";
+        let found: Vec<Vec<_>> = lint(input, &ASSUMPTIONS)
+            .into_iter()
+            .map(|(_, found)| found.iter().map(|d| (d.line, d.column)).collect())
+            .collect();
+        assert_eq!(
+            found,
+            [
+                vec![(3, 3)],
+                vec![(2, 1), (3, 1)],
+                vec![(3, 2)],
+                vec![(1, 3)]
+            ]
+        );
+        assert_eq!(one_start_and_end("abc")[0].message, "no S");
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let err = "abc\nab\nabc".parse::<Grid>().unwrap_err();
+        let d = err.downcast_ref::<Diagnostic>().unwrap();
+        assert_eq!((d.line, d.column), (2, 1), "{d}");
+        assert_eq!(d.message, "2 long, the first row is 3");
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day12.txt"), solve);
 }
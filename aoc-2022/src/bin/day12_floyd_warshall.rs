@@ -1,13 +1,17 @@
 use std::{
     collections::{HashSet, VecDeque},
     str::FromStr,
-    usize,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, ensure, Context, Result};
+use aoc_2022::budget;
+use aoc_2022::budget::Cancel;
+use aoc_2022::checked::{self, Overflow};
+use aoc_2022::solver;
 
 // This implements Floyd-Warshall, but it's hideously slow because the algorithm is O(V^3).
-// The challenge input is 3,280 vertices. So the inner loop runs roughly 3.5e10 times
+// The challenge input is 3,280 vertices. So the inner loop runs roughly 3.5e10 times, unless
+// AOC_BUDGET cuts it short
 
 fn main() -> Result<()> {
     for s in [
@@ -25,9 +29,13 @@ struct Grid {
 }
 
 impl Grid {
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
     fn coords(&self) -> impl Iterator<Item = Coord> + '_ {
         (0..self.rows.len()).flat_map(|x| {
-            (0..self.rows[0].len()).map(move |y| (x.try_into().unwrap(), y.try_into().unwrap()))
+            (0..self.width()).map(move |y| (x.try_into().unwrap(), y.try_into().unwrap()))
         })
     }
 
@@ -63,7 +71,12 @@ impl FromStr for Grid {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let rows = s.lines().map(|l| l.as_bytes().to_vec()).collect();
+        let rows: Vec<Vec<u8>> = s.lines().map(|l| l.as_bytes().to_vec()).collect();
+        let width = rows.first().map_or(0, Vec::len);
+        ensure!(
+            rows.iter().all(|r| r.len() == width),
+            "rows are not all {width} wide"
+        );
         Ok(Self { rows })
     }
 }
@@ -77,16 +90,17 @@ struct Graph {
     grid: Grid,
 }
 
-fn reach(from: u8, to: u8) -> bool {
+fn reach(from: u8, to: u8) -> Result<bool, Overflow> {
     // We obviously want the start to reach anything, and anything to reach the end.
     // Additionally, nothing can reach start and the end can reach nothing.
-    match (from, to) {
+    let r = match (from, to) {
         (b'S', _) => true,
         (b'E', _) => false,
-        (_, b'E') => b'z' <= (from + 1),
+        (_, b'E') => b'z' <= checked::add(solver!(), from, 1)?,
         (_, b'S') => false,
-        (from, to) => to <= (from + 1),
-    }
+        (from, to) => to <= checked::add(solver!(), from, 1)?,
+    };
+    Ok(r)
 }
 
 impl Graph {
@@ -100,7 +114,7 @@ impl Graph {
         for (dx, dy) in [(0, 1), (0, -1), (-1, 0), (1, 0)] {
             let (tx, ty) = (x + dx, y + dy);
             if let Some(to) = self.grid.get(tx, ty) {
-                if reach(from, to) {
+                if reach(from, to)? {
                     ret.push((tx, ty));
                 }
             }
@@ -109,7 +123,7 @@ impl Graph {
     }
 
     fn shortest_path(&self, x: isize, y: isize) -> Result<Option<usize>> {
-        let mut seen = HashSet::with_capacity(self.grid.rows.len() * self.grid.rows[0].len());
+        let mut seen = HashSet::with_capacity(self.grid.rows.len() * self.grid.width());
         let mut q = VecDeque::with_capacity(self.grid.rows.len());
         q.push_back(((x, y), 0));
         while let Some(((x, y), dist)) = q.pop_front() {
@@ -130,7 +144,7 @@ impl Graph {
         Ok(None)
     }
 
-    fn shortest_path_among(&self, coords: &[Coord]) -> Result<Option<usize>> {
+    fn shortest_path_among(&self, coords: &[Coord], cancel: &Cancel) -> Result<Option<usize>> {
         // init distance for all pairs
         let mut dist: Vec<Vec<Vec<Vec<_>>>> = self
             .grid
@@ -163,8 +177,7 @@ impl Graph {
         }
         // all shortest paths
         for (kx, ky) in all_coords.iter().copied() {
-            // print for progress
-            println!("{kx}, {ky}");
+            cancel.check()?;
             for (ix, iy) in all_coords.iter().copied() {
                 for (jx, jy) in all_coords.iter().copied() {
                     let new = dist[ix][iy][kx][ky].saturating_add(dist[kx][ky][jx][jy]);
@@ -206,7 +219,7 @@ fn part1(input: &str) -> Result<usize> {
 
 fn part2(input: &str) -> Result<usize> {
     let g: Graph = input.parse::<Grid>()?.try_into()?;
-    g.shortest_path_among(&g.grid.find_all(b'a'))?
+    g.shortest_path_among(&g.grid.find_all(b'a'), &budget!()?)?
         .ok_or_else(|| anyhow!("No path found"))
 }
 
@@ -220,6 +233,8 @@ fn solve(input: &str) -> Result<(usize, usize)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022::budget::Timeout;
+    use std::time::Duration;
 
     #[test]
     fn example() {
@@ -227,7 +242,20 @@ mod tests {
         assert_eq!(answer, (31, 29));
     }
 
-    // Don't test the challenge input. It's way too slow
+    // Don't test the challenge input, it's way too slow (and its table is ~86 MB). It should give
+    // up when asked to, which the example shows as well as anything.
+    #[test]
+    fn test_budget() {
+        let g: Graph = include_str!("../../data/example/day12.txt")
+            .parse::<Grid>()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let e = g
+            .shortest_path_among(&g.grid.find_all(b'a'), &Cancel::after(Duration::ZERO))
+            .unwrap_err();
+        assert!(e.is::<Timeout>(), "{e}");
+    }
 
     #[test]
     fn test_grid_coords() {
@@ -284,7 +312,7 @@ mod tests {
             (('x', 'z'), false),
             (('a', 'z'), false),
         ] {
-            assert_eq!(reach(from as u8, to as u8), want, "{from} -> {to}")
+            assert_eq!(reach(from as u8, to as u8), Ok(want), "{from} -> {to}")
         }
     }
 
@@ -329,4 +357,6 @@ mod tests {
             assert_eq!(got, want, "({x}, {y})")
         }
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day12.txt"), solve);
 }
@@ -4,6 +4,9 @@ use std::{
 };
 
 use anyhow::{anyhow, Context, Result};
+use aoc_2022::checked::{self, Overflow};
+use aoc_2022::diag::Diagnostic;
+use aoc_2022::solver;
 
 // For part 2, this runs a single BFS with edges inverted edges starting from 'E'
 
@@ -23,6 +26,10 @@ struct Grid {
 }
 
 impl Grid {
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
     fn get(&self, x: isize, y: isize) -> Option<u8> {
         if x < 0 || y < 0 {
             return None;
@@ -55,7 +62,12 @@ impl FromStr for Grid {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let rows = s.lines().map(|l| l.as_bytes().to_vec()).collect();
+        let rows: Vec<Vec<u8>> = s.lines().map(|l| l.as_bytes().to_vec()).collect();
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some((l, _)) = s.lines().zip(&rows).find(|(_, r)| r.len() != width) {
+            let msg = format!("{} long, the first row is {width}", l.len());
+            return Err(Diagnostic::new(s, l, msg).into());
+        }
         Ok(Self { rows })
     }
 }
@@ -69,24 +81,30 @@ struct Graph {
     grid: Grid,
 }
 
-fn reach(from: u8, to: u8) -> bool {
+fn reach(from: u8, to: u8) -> Result<bool, Overflow> {
     // We obviously want the start to reach anything, and anything to reach the end.
     // Additionally, nothing can reach start and the end can reach nothing.
-    match (from, to) {
+    let r = match (from, to) {
         (b'S', _) => true,
         (b'E', _) => false,
-        (_, b'E') => b'z' <= (from + 1),
+        (_, b'E') => b'z' <= checked::add(solver!(), from, 1)?,
         (_, b'S') => false,
-        (from, to) => to <= (from + 1),
-    }
+        (from, to) => to <= checked::add(solver!(), from, 1)?,
+    };
+    Ok(r)
 }
 
-fn reach_inv(from: u8, to: u8) -> bool {
+fn reach_inv(from: u8, to: u8) -> Result<bool, Overflow> {
     reach(to, from)
 }
 
 impl Graph {
-    fn adj(&self, x: isize, y: isize, check: fn(u8, u8) -> bool) -> Result<Vec<Coord>> {
+    fn adj(
+        &self,
+        x: isize,
+        y: isize,
+        check: fn(u8, u8) -> Result<bool, Overflow>,
+    ) -> Result<Vec<Coord>> {
         let from = self
             .grid
             .get(x, y)
@@ -96,7 +114,7 @@ impl Graph {
         for (dx, dy) in [(0, 1), (0, -1), (-1, 0), (1, 0)] {
             let (tx, ty) = (x + dx, y + dy);
             if let Some(to) = self.grid.get(tx, ty) {
-                if check(from, to) {
+                if check(from, to)? {
                     ret.push((tx, ty));
                 }
             }
@@ -105,7 +123,7 @@ impl Graph {
     }
 
     fn shortest_path(&self) -> Result<Option<usize>> {
-        let mut seen = HashSet::with_capacity(self.grid.rows.len() * self.grid.rows[0].len());
+        let mut seen = HashSet::with_capacity(self.grid.rows.len() * self.grid.width());
         let mut q = VecDeque::with_capacity(self.grid.rows.len());
         q.push_back(((self.start.0, self.start.1), 0));
         while let Some(((x, y), dist)) = q.pop_front() {
@@ -128,7 +146,7 @@ impl Graph {
 
     fn shortest_from_many(&self, starts: &[Coord]) -> Result<Option<usize>> {
         let num_rows = self.grid.rows.len();
-        let num_cols = self.grid.rows[0].len();
+        let num_cols = self.grid.width();
         let mut known = vec![vec![usize::MAX; num_cols]; num_rows];
         let mut q = VecDeque::with_capacity(num_cols);
 
@@ -150,7 +168,8 @@ impl Graph {
             .iter()
             .copied()
             .map(|(x, y)| known[x as usize][y as usize])
-            .min().and_then(|d| if d == usize::MAX { None } else { Some(d) }))
+            .min()
+            .filter(|&d| d != usize::MAX))
     }
 }
 
@@ -232,7 +251,7 @@ mod tests {
             (('x', 'z'), false),
             (('a', 'z'), false),
         ] {
-            assert_eq!(reach(from as u8, to as u8), want, "{from} -> {to}")
+            assert_eq!(reach(from as u8, to as u8), Ok(want), "{from} -> {to}")
         }
     }
 
@@ -280,4 +299,14 @@ mod tests {
             assert_eq!(got, want, "({x}, {y})")
         }
     }
+
+    #[test]
+    fn test_ragged_rows() {
+        let err = "abc\nab\nabc".parse::<Grid>().unwrap_err();
+        let d = err.downcast_ref::<Diagnostic>().unwrap();
+        assert_eq!((d.line, d.column), (2, 1), "{d}");
+        assert_eq!(d.message, "2 long, the first row is 3");
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day12.txt"), solve);
 }
@@ -1,16 +1,24 @@
 use std::cmp::Ordering;
 
-use anyhow::{Context, Result};
-use aoc_2022::nom_util::{run_parser, IResult};
+use anyhow::{bail, Context, Result};
+use aoc_2022::diag::Diagnostic;
+use aoc_2022::lint::{lint_arg, Assumption};
+use aoc_2022::nom_util::{blocks, run_parser, uint, IResult};
+use aoc_2022::trace::trace_arg;
 use nom::{
     branch::alt,
-    character::complete::{char, digit1, line_ending},
-    combinator::{map, map_res},
+    character::complete::{char, line_ending},
+    combinator::map,
     multi::separated_list0,
-    sequence::{delimited, separated_pair, tuple},
+    sequence::{delimited, separated_pair},
 };
+use tracing::instrument;
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
+    if lint_arg(&ASSUMPTIONS)? {
+        return Ok(());
+    }
     for s in [
         include_str!("../../data/example/day13.txt"),
         include_str!("../../data/challenge/day13.txt"),
@@ -26,9 +34,9 @@ enum P {
     L(Vec<P>),
 }
 
-fn packet(input: &str) -> IResult<P> {
+fn packet(input: &str) -> IResult<'_, P> {
     alt((
-        map_res(digit1, |s: &str| -> Result<P> { Ok(P::N(s.parse()?)) }),
+        map(uint, P::N),
         map(
             delimited(char('['), separated_list0(char(','), packet), char(']')),
             P::L,
@@ -36,11 +44,8 @@ fn packet(input: &str) -> IResult<P> {
     ))(input)
 }
 
-fn packet_pair_list(input: &str) -> IResult<Vec<(P, P)>> {
-    separated_list0(
-        tuple((line_ending, line_ending)),
-        separated_pair(packet, line_ending, packet),
-    )(input)
+fn packet_pair_list(input: &str) -> IResult<'_, Vec<(P, P)>> {
+    blocks(separated_pair(packet, line_ending, packet))(input)
 }
 
 fn compare_lists(x: &[P], y: &[P]) -> Ordering {
@@ -78,18 +83,98 @@ impl PartialOrd for P {
     }
 }
 
-fn part1(input: &str) -> Result<usize> {
-    Ok(run_parser(packet_pair_list, input)?
+// Checked by `aoc lint`, see src/lint.rs
+const ASSUMPTIONS: [Assumption; 3] = [
+    Assumption {
+        what: "packets come in pairs, separated by blank lines",
+        check: pairs,
+    },
+    Assumption {
+        what: "the packets in a pair never compare equal, which part 1 has no answer for",
+        check: unequal_pairs,
+    },
+    Assumption {
+        what: "no packet compares equal to a divider, [[2]] or [[6]]",
+        check: unlike_dividers,
+    },
+];
+
+// Runs of non-empty lines
+fn groups(input: &str) -> Vec<Vec<&str>> {
+    let mut groups = vec![vec![]];
+    for l in input.lines() {
+        match groups.last_mut() {
+            Some(g) if !l.is_empty() => g.push(l),
+            Some(g) if g.is_empty() => {}
+            _ => groups.push(vec![]),
+        }
+    }
+    groups.retain(|g| !g.is_empty());
+    groups
+}
+
+fn pairs(input: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for g in groups(input) {
+        if g.len() != 2 {
+            found.push(Diagnostic::new(input, g[0], format!("{} packets", g.len())));
+        }
+        for l in g {
+            if let Err(e) = run_parser(packet, l) {
+                found.push(Diagnostic::new(input, l, format!("not a packet: {e}")));
+            }
+        }
+    }
+    found
+}
+
+fn unequal_pairs(input: &str) -> Vec<Diagnostic> {
+    groups(input)
         .into_iter()
-        .enumerate()
-        .flat_map(|(i, (a, b))| match a.cmp(&b) {
-            Ordering::Less => Some(i + 1),
-            Ordering::Greater => None,
-            Ordering::Equal => panic!("{} was equal", i + 1),
+        .filter_map(|g| match g[..] {
+            [a, b] => {
+                let (x, y) = (run_parser(packet, a).ok()?, run_parser(packet, b).ok()?);
+                let equal = x.cmp(&y) == Ordering::Equal;
+                equal.then(|| Diagnostic::new(input, b, "compares equal to the packet before"))
+            }
+            _ => None,
         })
-        .sum())
+        .collect()
 }
 
+fn unlike_dividers(input: &str) -> Vec<Diagnostic> {
+    let dividers = [
+        P::L(vec![P::L(vec![P::N(2)])]),
+        P::L(vec![P::L(vec![P::N(6)])]),
+    ];
+    input
+        .lines()
+        .filter_map(|l| {
+            let p = run_parser(packet, l).ok()?;
+            let d = dividers.iter().find(|d| p.cmp(d) == Ordering::Equal)?;
+            Some(Diagnostic::new(
+                input,
+                l,
+                format!("compares equal to {d:?}"),
+            ))
+        })
+        .collect()
+}
+
+#[instrument(skip_all)]
+fn part1(input: &str) -> Result<usize> {
+    let mut sum = 0;
+    for (i, (a, b)) in run_parser(packet_pair_list, input)?.into_iter().enumerate() {
+        match a.cmp(&b) {
+            Ordering::Less => sum += i + 1,
+            Ordering::Greater => {}
+            Ordering::Equal => bail!("Pair {} was equal", i + 1),
+        }
+    }
+    Ok(sum)
+}
+
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     let div1 = run_parser(packet, "[[2]]").context("div1")?;
     let div2 = run_parser(packet, "[[6]]").context("div2")?;
@@ -107,6 +192,7 @@ fn part2(input: &str) -> Result<usize> {
     Ok(pos1 * pos2)
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<(usize, usize)> {
     let part1 = part1(input).context("part 1")?;
     let part2 = part2(input).context("part 2")?;
@@ -116,7 +202,7 @@ fn solve(input: &str) -> Result<(usize, usize)> {
 
 #[cfg(test)]
 mod tests {
-    use aoc_2022::nom_util::run_parser;
+    use aoc_2022::{lint::lint, nom_util::run_parser};
 
     use super::*;
 
@@ -179,4 +265,32 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn test_assumptions() {
+        for input in [
+            include_str!("../../data/example/day13.txt"),
+            include_str!("../../data/challenge/day13.txt"),
+        ] {
+            for (a, found) in lint(input, &ASSUMPTIONS) {
+                assert_eq!(found, [], "{}", a.what)
+            }
+        }
+
+        let input = "[1]\n[[1]]\n\n[2]\n\n[]\n[x]\n[6]\n";
+        let found: Vec<Vec<_>> = lint(input, &ASSUMPTIONS)
+            .into_iter()
+            .map(|(_, found)| found.iter().map(|d| (d.line, d.column)).collect())
+            .collect();
+        assert_eq!(
+            found,
+            [
+                vec![(4, 1), (6, 1), (7, 1)],
+                vec![(2, 1)],
+                vec![(4, 1), (8, 1)]
+            ]
+        );
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day13.txt"), solve);
 }
@@ -1,42 +1,141 @@
-#![feature(array_windows)]
-
-use std::cmp::{max, min};
+use std::{
+    cmp::{max, min},
+    ops::Range,
+    rc::Rc,
+};
 
 use anyhow::{ensure, Context, Result};
-use aoc_2022::nom_util::{run_parser, IResult};
+use aoc_2022::anim::{self, Frame};
+use aoc_2022::budget;
+use aoc_2022::budget::Cancel;
+use aoc_2022::checked;
+use aoc_2022::diag::Diagnostic;
+use aoc_2022::lint::{lint_arg, Assumption};
+use aoc_2022::nom_util::{lines, run_parser, uint, IResult};
+use aoc_2022::render::{render_arg, Image, Rgb};
+use aoc_2022::solver;
+use aoc_2022::trace::trace_arg;
 use nom::{
-    bytes::complete::tag,
-    character::complete::{char, digit1, line_ending},
-    combinator::map_res,
-    error::VerboseError,
-    multi::separated_list1,
+    bytes::complete::tag, character::complete::char, multi::separated_list1,
     sequence::separated_pair,
 };
+use tracing::instrument;
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
+    if lint_arg(&ASSUMPTIONS)? {
+        return Ok(());
+    }
     for s in [
         include_str!("../../data/example/day14.txt"),
         include_str!("../../data/challenge/day14.txt"),
     ] {
         println!("{:#?}", solve(s)?)
     }
+    if let Some(path) = render_arg() {
+        render(include_str!("../../data/challenge/day14.txt"))?.save(&path)?;
+    }
+    anim::from_args(|| frames(include_str!("../../data/challenge/day14.txt")))?;
     Ok(())
 }
 
 type RLine = Vec<(usize, usize)>;
 
-fn rock_line(input: &str) -> IResult<RLine> {
-    separated_list1(
-        tag::<_, _, VerboseError<_>>(" -> "),
-        map_res(
-            separated_pair(digit1::<&str, _>, char(','), digit1),
-            |(x, y)| -> Result<_> { Ok((x.parse()?, y.parse()?)) },
-        ),
-    )(input)
+fn rock_line(input: &str) -> IResult<'_, RLine> {
+    separated_list1(tag(" -> "), separated_pair(uint, char(','), uint))(input)
+}
+
+fn all_lines(input: &str) -> IResult<'_, Vec<RLine>> {
+    lines(rock_line)(input)
+}
+
+// Checked by `aoc lint`, see src/lint.rs
+const ASSUMPTIONS: [Assumption; 3] = [
+    Assumption {
+        what: "rock paths only run straight across or down, never diagonally",
+        check: straight_paths,
+    },
+    Assumption {
+        what: "the cave fits between the edges at x 0 and 999, including the part 2 pile of sand",
+        check: fits_width,
+    },
+    Assumption {
+        what: "there's no rock at the source of the sand, 500,0",
+        check: clear_source,
+    },
+];
+
+// Width of the grid. The edges count as the abyss.
+const WIDTH: usize = 1000;
+
+// Each point in the rock paths with its text. Anything that doesn't parse is left to the solver.
+fn rock_points(input: &str) -> Vec<Vec<(&str, (usize, usize))>> {
+    let point = |p: &str| {
+        let (x, y) = p.split_once(',')?;
+        Some((x.parse().ok()?, y.parse().ok()?))
+    };
+    input
+        .lines()
+        .map(|l| {
+            l.split(" -> ")
+                .filter_map(|p| Some((p, point(p)?)))
+                .collect()
+        })
+        .collect()
 }
 
-fn all_lines(input: &str) -> IResult<Vec<RLine>> {
-    separated_list1(line_ending, rock_line)(input)
+fn straight_paths(input: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for path in rock_points(input) {
+        for [(_, a), (span, b)] in path.array_windows() {
+            if a.0 != b.0 && a.1 != b.1 {
+                let msg = format!("diagonal from {},{}", a.0, a.1);
+                found.push(Diagnostic::new(input, span, msg));
+            }
+        }
+    }
+    found
+}
+
+fn fits_width(input: &str) -> Vec<Diagnostic> {
+    let points: Vec<_> = rock_points(input).into_iter().flatten().collect();
+    let mut found: Vec<_> = points
+        .iter()
+        .filter(|(_, (x, _))| *x >= WIDTH - 1)
+        .map(|(span, _)| Diagnostic::new(input, span, format!("x must be under {}", WIDTH - 1)))
+        .collect();
+    // With the floor two below the lowest rock, the pile spreads one further than that each way
+    if let Some((span, (_, y))) = points.iter().max_by_key(|(_, (_, y))| *y) {
+        let spread = y + 2;
+        if spread >= 500 || 500 + spread >= WIDTH - 1 {
+            let msg = format!(
+                "the part 2 pile would reach x {} to {}",
+                500 - spread as isize,
+                500 + spread
+            );
+            found.push(Diagnostic::new(input, span, msg));
+        }
+    }
+    found
+}
+
+fn clear_source(input: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for path in rock_points(input) {
+        // Segments ending at each point. A lone point is a segment on its own.
+        for (i, (span, b)) in path.iter().enumerate() {
+            if i == 0 && path.len() > 1 {
+                continue;
+            }
+            let a = path[i.saturating_sub(1)].1;
+            let xs = min(a.0, b.0)..=max(a.0, b.0);
+            let ys = min(a.1, b.1)..=max(a.1, b.1);
+            if xs.contains(&500) && ys.contains(&0) {
+                found.push(Diagnostic::new(input, span, "rock up to here covers 500,0"));
+            }
+        }
+    }
+    found
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,17 +153,27 @@ impl Grid {
         self
     }
 
-    fn fill_to_abyss(&mut self) -> usize {
+    fn fill_to_abyss(&mut self, cancel: &Cancel) -> Result<usize> {
+        self.fill_to_abyss_with(cancel, |_, _| {})
+    }
+
+    // Calls `on_rest` with where each grain settles, in order
+    fn fill_to_abyss_with(
+        &mut self,
+        cancel: &Cancel,
+        mut on_rest: impl FnMut(usize, usize),
+    ) -> Result<usize> {
         'grain: for n in 0.. {
+            cancel.check()?;
             let mut x = 500;
-            // Note: it's impossible for sand to rest on the last row (right???)
+            // Sand can't rest on the last row, there's nothing under it to rest on
             for y in 0..self.h - 1 {
                 if !self.g[y + 1][x] {
                     continue;
                 }
 
                 if x == 0 {
-                    return n;
+                    return Ok(n);
                 }
                 if !self.g[y + 1][x - 1] {
                     x -= 1;
@@ -72,7 +181,7 @@ impl Grid {
                 }
 
                 if x == self.w - 1 {
-                    return n;
+                    return Ok(n);
                 }
                 if !self.g[y + 1][x + 1] {
                     x += 1;
@@ -80,24 +189,27 @@ impl Grid {
                 }
 
                 self.g[y][x] = true;
+                on_rest(x, y);
                 continue 'grain;
             }
-            return n;
+            return Ok(n);
         }
         unreachable!()
     }
 
-    fn fill_to_top(&mut self) -> usize {
+    #[instrument(skip_all)]
+    fn fill_to_top(&mut self, cancel: &Cancel) -> Result<usize> {
         'grain: for n in 1.. {
+            cancel.check()?;
             let mut x = 500;
-            // Note: it's impossible for sand to rest on the last row (right???)
+            // Sand can't get into the last row, it's the floor
             for y in 0..self.h - 1 {
                 if !self.g[y + 1][x] {
                     continue;
                 }
 
                 if x == 0 {
-                    return n;
+                    return Ok(n);
                 }
                 if !self.g[y + 1][x - 1] {
                     x -= 1;
@@ -105,7 +217,7 @@ impl Grid {
                 }
 
                 if x == self.w - 1 {
-                    return n;
+                    return Ok(n);
                 }
                 if !self.g[y + 1][x + 1] {
                     x += 1;
@@ -114,7 +226,7 @@ impl Grid {
 
                 self.g[y][x] = true;
                 if y == 0 && x == 500 {
-                    return n;
+                    return Ok(n);
                 }
                 continue 'grain;
             }
@@ -128,12 +240,14 @@ impl TryFrom<Vec<RLine>> for Grid {
 
     fn try_from(lines: Vec<RLine>) -> Result<Self> {
         ensure!(lines.iter().flatten().next().is_some(), "No points");
-        let want_w = 1 + lines.iter().flatten().map(|(x, _)| x).max().unwrap();
-        let h = 1 + lines.iter().flatten().map(|(_, y)| y).max().unwrap();
+        let max_x = lines.iter().flatten().map(|(x, _)| *x).max().unwrap();
+        let max_y = lines.iter().flatten().map(|(_, y)| *y).max().unwrap();
+        let want_w = checked::add(solver!(), max_x, 1)?;
+        let h = checked::add(solver!(), max_y, 1)?;
 
         // Part 2 requires the area to extend further. There isn't an obvious way to pick a number
         // based on input data.
-        let w = 1000;
+        let w = WIDTH;
         ensure!(want_w < w, "Rock outside area");
         let mut g = vec![vec![false; w]; h];
         for l in lines {
@@ -151,15 +265,17 @@ impl TryFrom<Vec<RLine>> for Grid {
     }
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str) -> Result<usize> {
     let mut g: Grid = run_parser(all_lines, input)?.try_into()?;
-    Ok(g.fill_to_abyss())
+    g.fill_to_abyss(&budget!()?)
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str) -> Result<usize> {
     let mut g: Grid = run_parser(all_lines, input)?.try_into()?;
     g = g.floored();
-    let n = g.fill_to_top();
+    let n = g.fill_to_top(&budget!()?)?;
     // for r in g.g.iter() {
     //     let s: String = (&r[488..])
     //         .iter()
@@ -171,6 +287,93 @@ fn part2(input: &str) -> Result<usize> {
     Ok(n)
 }
 
+// Rock in grey, sand from part 1 in gold, and the extra sand from part 2 in tan
+fn render(input: &str) -> Result<Image> {
+    const AIR: Rgb = [20, 20, 30];
+    const ROCK: Rgb = [120, 120, 120];
+    const SAND_1: Rgb = [230, 180, 40];
+    const SAND_2: Rgb = [200, 160, 110];
+
+    let rock: Grid = run_parser(all_lines, input)?.try_into()?;
+    let mut abyss = rock.clone();
+    abyss.fill_to_abyss(&Cancel::never())?;
+    let mut floored = rock.clone().floored();
+    floored.fill_to_top(&Cancel::never())?;
+
+    // Sand piles up in a triangle under the source, so that's all that's worth drawing
+    let h = floored.h;
+    let (lo, hi) = (500_usize.saturating_sub(h + 1), min(floored.w, 500 + h + 2));
+    let img = Image::from_fn(hi - lo, h, |x, y| {
+        let x = x + lo;
+        if y == h - 1 || (y < rock.h && rock.g[y][x]) {
+            ROCK
+        } else if y < abyss.h && abyss.g[y][x] {
+            SAND_1
+        } else if floored.g[y][x] {
+            SAND_2
+        } else {
+            AIR
+        }
+    });
+    Ok(img.scaled(2))
+}
+
+// The cave once `grains` grains have come to rest, cropped to the columns with rock in them
+struct SandFrame {
+    rock: Rc<Grid>,
+    sand: Rc<Vec<(usize, usize)>>,
+    grains: usize,
+    xs: Range<usize>,
+}
+
+impl Frame for SandFrame {
+    fn draw(&self) -> String {
+        let mut rows: Vec<Vec<char>> = self.rock.g[..]
+            .iter()
+            .map(|r| {
+                r[self.xs.clone()]
+                    .iter()
+                    .map(|&b| if b { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        if let Some(row) = rows.first_mut() {
+            row[500 - self.xs.start] = '+';
+        }
+        for &(x, y) in &self.sand[..self.grains] {
+            rows[y][x - self.xs.start] = 'o';
+        }
+        let mut out = format!("{} grains\n", self.grains);
+        for r in rows {
+            out.extend(r);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn frames(input: &str) -> Result<Vec<SandFrame>> {
+    let rock: Grid = run_parser(all_lines, input)?.try_into()?;
+    let mut sand = Vec::new();
+    rock.clone()
+        .fill_to_abyss_with(&Cancel::never(), |x, y| sand.push((x, y)))?;
+
+    // Sand can't settle outside the rock (it falls into the abyss), but the source could be
+    let cols = || (0..rock.w).filter(|&x| x == 500 || rock.g.iter().any(|r| r[x]));
+    let xs = cols().min().unwrap()..cols().max().unwrap() + 1;
+    let (rock, sand) = (Rc::new(rock), Rc::new(sand));
+    let frames = (0..=sand.len())
+        .map(|grains| SandFrame {
+            rock: rock.clone(),
+            sand: sand.clone(),
+            grains,
+            xs: xs.clone(),
+        })
+        .collect();
+    Ok(frames)
+}
+
+#[instrument(skip_all)]
 fn solve(input: &str) -> Result<(usize, usize)> {
     let part1 = part1(input).context("part 1")?;
     let part2 = part2(input).context("part 2")?;
@@ -181,6 +384,7 @@ fn solve(input: &str) -> Result<(usize, usize)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2022::lint::lint;
 
     #[test]
     fn example() {
@@ -193,4 +397,65 @@ mod tests {
         let answer = solve(include_str!("../../data/challenge/day14.txt")).unwrap();
         assert_eq!(answer, (1199, 23925));
     }
+
+    #[test]
+    fn test_frames() {
+        let frames = frames(include_str!("../../data/example/day14.txt")).unwrap();
+        assert_eq!(frames.len(), 25);
+        assert_eq!(
+            frames[5].draw(),
+            "5 grains
+......+...
+..........
+..........
+..........
+....#...##
+....#...#.
+..###...#.
+......o.#.
+....oooo#.
+#########.
+"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let img = render(include_str!("../../data/example/day14.txt")).unwrap();
+        // 12 rows including the floor, 500 +/- 13 columns
+        assert_eq!((img.width(), img.height()), (54, 24));
+        let at = |x: usize, y: usize| img.get((x - 487) * 2, y * 2).unwrap();
+        assert_eq!(at(500, 0), [200, 160, 110]);
+        assert_eq!(at(500, 2), [230, 180, 40]);
+        assert_eq!(at(498, 4), [120, 120, 120]);
+        assert_eq!(at(488, 0), [20, 20, 30]);
+    }
+
+    #[test]
+    fn test_assumptions() {
+        for input in [
+            include_str!("../../data/example/day14.txt"),
+            include_str!("../../data/challenge/day14.txt"),
+        ] {
+            for (a, found) in lint(input, &ASSUMPTIONS) {
+                assert_eq!(found, [], "{}", a.what)
+            }
+        }
+
+        let input = "490,5 -> 492,7 -> 999,7\n500,0 -> 500,2\n498,0 -> 502,0\n500,0\n10,600";
+        let found: Vec<Vec<_>> = lint(input, &ASSUMPTIONS)
+            .into_iter()
+            .map(|(_, found)| found.iter().map(|d| (d.line, d.column)).collect())
+            .collect();
+        assert_eq!(
+            found,
+            [
+                vec![(1, 10)],
+                vec![(1, 19), (5, 1)],
+                vec![(2, 10), (3, 10), (4, 1)]
+            ]
+        );
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day14.txt"), solve);
 }
@@ -1,14 +1,19 @@
-use anyhow::{bail, ensure, Context, Result};
-use aoc_2022::nom_util::{run_parser, IResult};
-use nom::{
-    bytes::complete::tag,
-    character::complete::{char, digit1, line_ending},
-    combinator::{map_res, opt},
-    multi::separated_list1,
-    sequence::{pair, tuple},
-};
+use std::cmp::{max, min};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use aoc_2022::budget;
+use aoc_2022::budget::Cancel;
+use aoc_2022::checked::{self, Overflow};
+use aoc_2022::nom_util::{int, lines, run_parser, IResult};
+use aoc_2022::par;
+use aoc_2022::render::{render_arg, Image, Palette, BLACK, RED, WHITE};
+use aoc_2022::solver;
+use aoc_2022::trace::trace_arg;
+use nom::{bytes::complete::tag, combinator::map_res, sequence::tuple};
+use tracing::instrument;
 
 fn main() -> Result<()> {
+    let _trace = trace_arg()?;
     for (s, p1_y, p2_max_n) in [
         (include_str!("../../data/example/day15.txt"), 10, 20),
         (
@@ -19,6 +24,9 @@ fn main() -> Result<()> {
     ] {
         println!("{:#?}", solve(s, p1_y, p2_max_n)?)
     }
+    if let Some(path) = render_arg() {
+        render(include_str!("../../data/challenge/day15.txt"), 4000000)?.save(&path)?;
+    }
     Ok(())
 }
 
@@ -33,8 +41,15 @@ impl Point {
         Self { x, y }
     }
 
-    fn mdist(&self, other: &Point) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
+    fn mdist(&self, other: &Point) -> Result<i32, Overflow> {
+        // This is the innermost loop, so skip the checked helpers unless they'll do something
+        if !checked::ENABLED {
+            let dx = self.x.wrapping_sub(other.x).wrapping_abs();
+            return Ok(dx.wrapping_add(self.y.wrapping_sub(other.y).wrapping_abs()));
+        }
+        let dx = checked::abs(solver!(), checked::sub(solver!(), self.x, other.x)?)?;
+        let dy = checked::abs(solver!(), checked::sub(solver!(), self.y, other.y)?)?;
+        checked::add(solver!(), dx, dy)
     }
 }
 
@@ -45,35 +60,22 @@ struct Sensor {
 }
 
 impl Sensor {
-    fn refutes(&self, loc: &Point) -> bool {
-        self.pos.mdist(loc) <= self.pos.mdist(&self.closest)
+    fn refutes(&self, loc: &Point) -> Result<bool, Overflow> {
+        Ok(self.pos.mdist(loc)? <= self.pos.mdist(&self.closest)?)
     }
 }
 
-fn signed_int(input: &str) -> IResult<i32> {
-    map_res(
-        pair(opt(char::<&str, _>('-')), digit1),
-        |(sign, num)| -> Result<i32> {
-            let mut val: i32 = num.parse().context("signed int")?;
-            if sign.is_some() {
-                val *= -1;
-            }
-            Ok(val)
-        },
-    )(input)
-}
-
-fn single_sensor(input: &str) -> IResult<Sensor> {
+fn single_sensor(input: &str) -> IResult<'_, Sensor> {
     map_res(
         tuple((
             tag("Sensor at x="),
-            signed_int,
+            int,
             tag(", y="),
-            signed_int,
+            int,
             tag(": closest beacon is at x="),
-            signed_int,
+            int,
             tag(", y="),
-            signed_int,
+            int,
         )),
         |(_, px, _, py, _, bx, _, by)| -> Result<Sensor> {
             Ok(Sensor {
@@ -84,60 +86,143 @@ fn single_sensor(input: &str) -> IResult<Sensor> {
     )(input)
 }
 
-fn parse_sensors(input: &str) -> IResult<Vec<Sensor>> {
-    separated_list1(line_ending, single_sensor)(input)
+fn parse_sensors(input: &str) -> IResult<'_, Vec<Sensor>> {
+    lines(single_sensor)(input)
 }
 
+#[instrument(skip_all)]
 fn part1(input: &str, query_y: i32) -> Result<usize> {
     let sensors = run_parser(parse_sensors, input)?;
     ensure!(!sensors.is_empty(), "No sensors");
-    let min_x = sensors
+    let spans = sensors
         .iter()
-        .map(|s| s.closest.x - s.pos.mdist(&s.closest))
-        .min()
-        .unwrap();
-    let max_x = sensors
-        .iter()
-        .map(|s| s.closest.x + s.pos.mdist(&s.closest))
-        .max()
-        .unwrap();
-    Ok((min_x..=max_x)
-        .filter(|x| {
-            let p = Point::new(*x, query_y);
-            let is_beacon = sensors.iter().any(|s| s.closest == p);
-            let refuted = sensors.iter().any(|s| s.refutes(&p));
-            !is_beacon && refuted
+        .map(|s| {
+            let d = s.pos.mdist(&s.closest)?;
+            Ok((
+                checked::sub(solver!(), s.closest.x, d)?,
+                checked::add(solver!(), s.closest.x, d)?,
+            ))
         })
-        .count())
+        .collect::<Result<Vec<_>, Overflow>>()?;
+    let min_x = spans.iter().map(|s| s.0).min().unwrap();
+    let max_x = spans.iter().map(|s| s.1).max().unwrap();
+    let cancel = budget!()?;
+    let count = (min_x..=max_x).try_fold(0, |n, x| {
+        // Columns are cheap, but there can be millions of them
+        if x % 1024 == 0 {
+            cancel.check()?;
+        }
+        let p = Point::new(x, query_y);
+        let is_beacon = sensors.iter().any(|s| s.closest == p);
+        let refuted = any_refutes(&sensors, &p)?;
+        Ok::<_, anyhow::Error>(n + usize::from(!is_beacon && refuted))
+    })?;
+    Ok(count)
+}
+
+fn any_refutes(sensors: &[Sensor], p: &Point) -> Result<bool, Overflow> {
+    for s in sensors {
+        if s.refutes(p)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Each sensor's perimeter is searched separately, taking the first sensor (in input order) with a
+// gap, so the answer doesn't depend on which search finishes first
+fn find_gap(sensors: &[Sensor], max_n: i32, cancel: &Cancel) -> Result<Option<Point>> {
+    par::find_map_first(sensors, |s| {
+        let around = cancel
+            .check()
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(find_gap_around(sensors, s, max_n)?));
+        around.transpose()
+    })
+    .transpose()
+}
+
+// Just outside the range of `s` is where a single uncovered point has to be
+fn find_gap_around(sensors: &[Sensor], s: &Sensor, max_n: i32) -> Result<Option<Point>, Overflow> {
+    let dist = checked::add(solver!(), s.pos.mdist(&s.closest)?, 1)?;
+    for dx in (-dist)..=dist {
+        let remaining = checked::sub(solver!(), dist, dx)?;
+        let x = checked::add(solver!(), s.pos.x, dx)?;
+        if x < 0 || x > max_n {
+            continue;
+        }
+
+        for y in [
+            checked::sub(solver!(), s.pos.y, remaining)?,
+            checked::add(solver!(), s.pos.y, remaining)?,
+        ] {
+            if y < 0 || y > max_n {
+                continue;
+            }
+            let p = Point::new(x, y);
+            if !any_refutes(sensors, &p)? {
+                return Ok(Some(p));
+            }
+        }
+    }
+    Ok(None)
 }
 
+#[instrument(skip_all)]
 fn part2(input: &str, max_n: i32) -> Result<i64> {
     let sensors = run_parser(parse_sensors, input)?;
     ensure!(!sensors.is_empty(), "No sensors");
 
-    for s in sensors.iter() {
-        let dist = s.pos.mdist(&s.closest) + 1;
-        for dx in (-dist)..=dist {
-            let remaining = dist - dx;
-            let x = s.pos.x + dx;
-            if x < 0 || x > max_n {
-                continue;
-            }
+    let p = find_gap(&sensors, max_n, &budget!()?)?.ok_or_else(|| anyhow!("No point found"))?;
+    Ok(p.x as i64 * 4000000 + p.y as i64)
+}
+
+// Sensor coverage of the part 2 area, brighter where more sensors overlap. Sensors are white,
+// beacons yellow and the gap from part 2 red. Large areas are sampled down to 800 pixels a side.
+fn render(input: &str, max_n: i32) -> Result<Image> {
+    let sensors = run_parser(parse_sensors, input)?;
+    ensure!(max_n >= 0, "max_n is {max_n}, must not be negative");
+
+    let size = min(max_n as usize + 1, 800);
+    let cell = (max_n as f64 + 1.) / size as f64;
+    let coverage = Palette::gradient([20, 30, 70], [120, 200, 255], 6);
+    let mut overflow = None;
+    let mut img = Image::from_fn(size, size, |x, y| {
+        let p = Point::new((x as f64 * cell) as i32, (y as f64 * cell) as i32);
+        let covering = sensors.iter().filter(|s| {
+            s.refutes(&p).unwrap_or_else(|e| {
+                overflow.get_or_insert(e);
+                false
+            })
+        });
+        match covering.count() {
+            0 => BLACK,
+            n => coverage.get(n - 1),
+        }
+    });
+    if let Some(e) = overflow {
+        return Err(e.into());
+    }
 
-            for y in [s.pos.y - remaining, s.pos.y + remaining] {
-                if y < 0 || y > max_n {
-                    continue;
-                }
-                let p = Point::new(x, y);
-                if !sensors.iter().any(|s| s.refutes(&p)) {
-                    return Ok(x as i64 * 4000000 + y as i64);
-                }
+    let mut mark = |p: &Point, color| {
+        if (0..=max_n).contains(&p.x) && (0..=max_n).contains(&p.y) {
+            let (x, y) = ((p.x as f64 / cell) as usize, (p.y as f64 / cell) as usize);
+            for (x, y) in [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+                img.set(x, y, color)
             }
         }
+    };
+    for s in sensors.iter() {
+        mark(&s.pos, WHITE);
+        mark(&s.closest, [240, 220, 60]);
+    }
+    if let Some(p) = find_gap(&sensors, max_n, &Cancel::never())? {
+        mark(&p, RED);
     }
-    bail!("No point found")
+    Ok(img.scaled(max(1, 400 / size)))
 }
 
+#[instrument(skip_all)]
 fn solve(input: &str, p1_y: i32, p2_max_n: i32) -> Result<(usize, i64)> {
     let part1 = part1(input, p1_y).context("part 1")?;
     let part2 = part2(input, p2_max_n).context("part 2")?;
@@ -175,8 +260,8 @@ mod tests {
             (Point::new(0, 0), Point::new(10, 1), 11),
             (Point::new(0, 0), Point::new(2, 22), 24),
         ] {
-            assert_eq!(a.mdist(&b), want, "{a:?} {b:?}");
-            assert_eq!(b.mdist(&a), want, "{b:?} {a:?}");
+            assert_eq!(a.mdist(&b), Ok(want), "{a:?} {b:?}");
+            assert_eq!(b.mdist(&a), Ok(want), "{b:?} {a:?}");
         }
     }
 
@@ -232,7 +317,31 @@ mod tests {
                 false,
             ),
         ] {
-            assert_eq!(beacon.refutes(&pos), want, "{beacon:?} {pos:?}");
+            assert_eq!(beacon.refutes(&pos), Ok(want), "{beacon:?} {pos:?}");
         }
     }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let input = "Sensor at x=-2000000000, y=0: closest beacon is at x=2000000000, y=0";
+        let err = part1(input, 0).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "day15: overflow in -2000000000 - 2000000000 (i32)"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let img = render(include_str!("../../data/example/day15.txt"), 20).unwrap();
+        // 21x21 cells, scaled by 19
+        assert_eq!((img.width(), img.height()), (399, 399));
+        assert_eq!(img.get(14 * 19, 11 * 19), Some(RED));
+        assert_eq!(img.get(8 * 19, 7 * 19), Some(WHITE));
+    }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day15.txt"), |s| {
+        solve(s, 10, 20)
+    });
 }
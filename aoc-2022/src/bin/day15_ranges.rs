@@ -1,14 +1,10 @@
 use std::{cmp::max, collections::HashSet};
 
 use anyhow::{bail, ensure, Context, Result};
-use aoc_2022::nom_util::{run_parser, IResult};
-use nom::{
-    bytes::complete::tag,
-    character::complete::{char, digit1, line_ending},
-    combinator::{map_res, opt},
-    multi::separated_list1,
-    sequence::{pair, tuple},
-};
+use aoc_2022::checked::{self, Overflow};
+use aoc_2022::nom_util::{int, lines, run_parser, IResult};
+use aoc_2022::solver;
+use nom::{bytes::complete::tag, combinator::map_res, sequence::tuple};
 
 fn main() -> Result<()> {
     for (s, p1_y, p2_max_n) in [
@@ -35,8 +31,10 @@ impl Point {
         Self { x, y }
     }
 
-    fn mdist(&self, other: &Point) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
+    fn mdist(&self, other: &Point) -> Result<i32, Overflow> {
+        let dx = checked::abs(solver!(), checked::sub(solver!(), self.x, other.x)?)?;
+        let dy = checked::abs(solver!(), checked::sub(solver!(), self.y, other.y)?)?;
+        checked::add(solver!(), dx, dy)
     }
 }
 
@@ -47,35 +45,22 @@ struct Sensor {
 }
 
 impl Sensor {
-    fn refutes(&self, loc: &Point) -> bool {
-        self.pos.mdist(loc) <= self.pos.mdist(&self.closest)
+    fn refutes(&self, loc: &Point) -> Result<bool, Overflow> {
+        Ok(self.pos.mdist(loc)? <= self.pos.mdist(&self.closest)?)
     }
 }
 
-fn signed_int(input: &str) -> IResult<i32> {
-    map_res(
-        pair(opt(char::<&str, _>('-')), digit1),
-        |(sign, num)| -> Result<i32> {
-            let mut val: i32 = num.parse().context("signed int")?;
-            if sign.is_some() {
-                val *= -1;
-            }
-            Ok(val)
-        },
-    )(input)
-}
-
-fn single_sensor(input: &str) -> IResult<Sensor> {
+fn single_sensor(input: &str) -> IResult<'_, Sensor> {
     map_res(
         tuple((
             tag("Sensor at x="),
-            signed_int,
+            int,
             tag(", y="),
-            signed_int,
+            int,
             tag(": closest beacon is at x="),
-            signed_int,
+            int,
             tag(", y="),
-            signed_int,
+            int,
         )),
         |(_, px, _, py, _, bx, _, by)| -> Result<Sensor> {
             Ok(Sensor {
@@ -86,8 +71,8 @@ fn single_sensor(input: &str) -> IResult<Sensor> {
     )(input)
 }
 
-fn parse_sensors(input: &str) -> IResult<Vec<Sensor>> {
-    separated_list1(line_ending, single_sensor)(input)
+fn parse_sensors(input: &str) -> IResult<'_, Vec<Sensor>> {
+    lines(single_sensor)(input)
 }
 
 fn merge_ranges(mut ranges: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
@@ -116,22 +101,23 @@ fn part1(input: &str, query_y: i32) -> Result<usize> {
     let sensors = run_parser(parse_sensors, input)?;
     ensure!(!sensors.is_empty(), "No sensors");
 
-    let ranges: Vec<(i32, i32)> = sensors
-        .iter()
-        .flat_map(|s| {
-            let dx = s.pos.mdist(&s.closest) - (s.pos.y - query_y).abs();
-            if dx < 0 {
-                None
-            } else {
-                Some((s.pos.x - dx, s.pos.x + dx))
-            }
-        })
-        .collect();
+    let mut ranges = Vec::new();
+    for s in &sensors {
+        let dy = checked::abs(solver!(), checked::sub(solver!(), s.pos.y, query_y)?)?;
+        let dx = checked::sub(solver!(), s.pos.mdist(&s.closest)?, dy)?;
+        if dx >= 0 {
+            ranges.push((
+                checked::sub(solver!(), s.pos.x, dx)?,
+                checked::add(solver!(), s.pos.x, dx)?,
+            ));
+        }
+    }
 
-    let refuted: i32 = merge_ranges(ranges)
+    // Widths as i64, which two i32s apart always fit in
+    let widths = merge_ranges(ranges)
         .into_iter()
-        .map(|r| r.1 - r.0 + 1)
-        .sum();
+        .map(|r| r.1 as i64 - r.0 as i64 + 1);
+    let refuted = usize::try_from(checked::sum(solver!(), 0, widths)?)?;
 
     let num_beacons = sensors
         .iter()
@@ -145,7 +131,7 @@ fn part1(input: &str, query_y: i32) -> Result<usize> {
         .collect::<HashSet<_>>()
         .len();
 
-    Ok(refuted as usize - num_beacons)
+    Ok(checked::sub(solver!(), refuted, num_beacons)?)
 }
 
 fn part2(input: &str, max_n: i32) -> Result<i64> {
@@ -153,20 +139,23 @@ fn part2(input: &str, max_n: i32) -> Result<i64> {
     ensure!(!sensors.is_empty(), "No sensors");
 
     for s in sensors.iter() {
-        let dist = s.pos.mdist(&s.closest) + 1;
+        let dist = checked::add(solver!(), s.pos.mdist(&s.closest)?, 1)?;
         for dx in (-dist)..=dist {
-            let remaining = dist - dx;
-            let x = s.pos.x + dx;
+            let remaining = checked::sub(solver!(), dist, dx)?;
+            let x = checked::add(solver!(), s.pos.x, dx)?;
             if x < 0 || x > max_n {
                 continue;
             }
 
-            for y in [s.pos.y - remaining, s.pos.y + remaining] {
+            for y in [
+                checked::sub(solver!(), s.pos.y, remaining)?,
+                checked::add(solver!(), s.pos.y, remaining)?,
+            ] {
                 if y < 0 || y > max_n {
                     continue;
                 }
                 let p = Point::new(x, y);
-                if !sensors.iter().any(|s| s.refutes(&p)) {
+                if !refuted_by_any(&sensors, &p)? {
                     return Ok(x as i64 * 4000000 + y as i64);
                 }
             }
@@ -175,6 +164,15 @@ fn part2(input: &str, max_n: i32) -> Result<i64> {
     bail!("No point found")
 }
 
+fn refuted_by_any(sensors: &[Sensor], p: &Point) -> Result<bool, Overflow> {
+    for s in sensors {
+        if s.refutes(p)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 fn solve(input: &str, p1_y: i32, p2_max_n: i32) -> Result<(usize, i64)> {
     let part1 = part1(input, p1_y).context("part 1")?;
     let part2 = part2(input, p2_max_n).context("part 2")?;
@@ -212,8 +210,8 @@ mod tests {
             (Point::new(0, 0), Point::new(10, 1), 11),
             (Point::new(0, 0), Point::new(2, 22), 24),
         ] {
-            assert_eq!(a.mdist(&b), want, "{a:?} {b:?}");
-            assert_eq!(b.mdist(&a), want, "{b:?} {a:?}");
+            assert_eq!(a.mdist(&b), Ok(want), "{a:?} {b:?}");
+            assert_eq!(b.mdist(&a), Ok(want), "{b:?} {a:?}");
         }
     }
 
@@ -284,7 +282,11 @@ mod tests {
                 false,
             ),
         ] {
-            assert_eq!(beacon.refutes(&pos), want, "{beacon:?} {pos:?}");
+            assert_eq!(beacon.refutes(&pos), Ok(want), "{beacon:?} {pos:?}");
         }
     }
+
+    aoc_2022::test_no_panics!(include_str!("../../data/example/day15.txt"), |s| {
+        solve(s, 10, 20)
+    });
 }
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use aoc_2022::diag::Diagnostic;
+use aoc_2022_core::{day01, day02, day03, day04, day06, Error};
+
+// Runs the no_std, allocation-free solvers from aoc-2022-core (days 1, 2, 3, 4 and 6), turning
+// their errors into diagnostics. The other binaries for these days are the regular solutions.
+
+type Solver = fn(&[u8]) -> Result<(u32, u32), Error>;
+
+fn day06(input: &[u8]) -> Result<(u32, u32), Error> {
+    let (a, b) = day06::solve(input)?;
+    // A marker past u32::MAX would need a 4GiB input
+    Ok((a as u32, b as u32))
+}
+
+const DAYS: [(&str, Solver, &str); 5] = [
+    (
+        "day01",
+        day01::solve,
+        include_str!("../../data/challenge/day01.txt"),
+    ),
+    (
+        "day02",
+        day02::solve,
+        include_str!("../../data/challenge/day02.txt"),
+    ),
+    (
+        "day03",
+        day03::solve,
+        include_str!("../../data/challenge/day03.txt"),
+    ),
+    (
+        "day04",
+        day04::solve,
+        include_str!("../../data/challenge/day04.txt"),
+    ),
+    (
+        "day06",
+        day06,
+        include_str!("../../data/challenge/day06.txt"),
+    ),
+];
+
+fn main() -> Result<()> {
+    for (name, solve, input) in DAYS {
+        let answer = run(solve, input).with_context(|| name.to_owned())?;
+        println!("{name}: {answer:?}");
+    }
+    Ok(())
+}
+
+fn run(solve: Solver, input: &str) -> Result<(u32, u32)> {
+    Ok(solve(input.as_bytes()).map_err(|e| Diagnostic::from_core(input, e))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_2022::fuzz::assert_no_panics;
+
+    #[test]
+    fn example() {
+        let inputs = [
+            include_str!("../../data/example/day01.txt"),
+            include_str!("../../data/example/day02.txt"),
+            include_str!("../../data/example/day03.txt"),
+            include_str!("../../data/example/day04.txt"),
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        ];
+        let answers = [(24000, 45000), (15, 12), (157, 70), (2, 4), (7, 19)];
+        for (((name, solve, _), input), want) in DAYS.into_iter().zip(inputs).zip(answers) {
+            assert_eq!(run(solve, input).unwrap(), want, "{name}")
+        }
+    }
+
+    #[test]
+    fn test_no_panics() {
+        let inputs = [
+            include_str!("../../data/example/day01.txt"),
+            include_str!("../../data/example/day02.txt"),
+            include_str!("../../data/example/day03.txt"),
+            include_str!("../../data/example/day04.txt"),
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        ];
+        for ((_, solve, _), input) in DAYS.into_iter().zip(inputs) {
+            assert_no_panics(input, |s| run(solve, s));
+        }
+    }
+
+    #[test]
+    fn challenge() {
+        let answers: Vec<_> = DAYS
+            .into_iter()
+            .map(|(_, solve, input)| run(solve, input).unwrap())
+            .collect();
+        assert_eq!(
+            answers,
+            [
+                (68787, 198041),
+                (12772, 11618),
+                (7446, 2646),
+                (477, 830),
+                (1275, 3605)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let err = run(day03::solve, "abcb\nabc").unwrap_err();
+        let d = err.downcast_ref::<Diagnostic>().unwrap();
+        assert_eq!(
+            (d.line, d.column, d.message.as_str()),
+            (2, 1, "line length must be even")
+        );
+    }
+}
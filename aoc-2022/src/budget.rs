@@ -0,0 +1,154 @@
+use std::{
+    env, fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+
+// Time budget for each part, e.g. `AOC_BUDGET=5s cargo test`, for the parts that check it: those
+// with loops that can run long, in days 11, 12, 14 and 15. Unset means no limit, except in tests,
+// where it's TEST_BUDGET so a runaway loop fails instead of hanging.
+pub const ENV: &str = "AOC_BUDGET";
+
+// Generous enough for every part in a debug build.
+pub const TEST_BUDGET: Duration = Duration::from_secs(60);
+
+// A cooperative cancellation token. Long loops call `check` every so often, which fails once the
+// time budget is spent or `cancel` has been called (from any clone, on any thread).
+#[derive(Debug, Clone)]
+pub struct Cancel {
+    start: Instant,
+    budget: Option<Duration>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Cancel {
+    pub fn new(budget: Option<Duration>) -> Self {
+        Self {
+            start: Instant::now(),
+            budget,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn never() -> Self {
+        Self::new(None)
+    }
+
+    pub fn after(budget: Duration) -> Self {
+        Self::new(Some(budget))
+    }
+
+    // Starts the clock on the budget from `AOC_BUDGET`, or `default` if it's unset, so call it at
+    // the start of a part. Solvers use `budget!()` instead, which picks the default for them.
+    pub fn from_env(default: Option<Duration>) -> Result<Self> {
+        let budget = match env::var(ENV) {
+            Ok(s) => Some(parse_duration(&s).with_context(|| format!("bad {ENV}"))?),
+            Err(_) => default,
+        };
+        Ok(Self::new(budget))
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn check(&self) -> Result<(), Timeout> {
+        let elapsed = self.start.elapsed();
+        let over = self.budget.is_some_and(|b| elapsed > b);
+        if over || self.cancelled.load(Ordering::Relaxed) {
+            return Err(Timeout {
+                elapsed,
+                budget: self.budget,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    pub elapsed: Duration,
+    pub budget: Option<Duration>,
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TIMEOUT after {:.2?}", self.elapsed)?;
+        match self.budget {
+            Some(b) => write!(f, " (budget {b:?})"),
+            None => write!(f, " (cancelled)"),
+        }
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+// "250ms", "10s", "2m", or a bare number of seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: f64 = n
+        .parse()
+        .with_context(|| format!("{s:?} doesn't start with a number"))?;
+    let secs = match unit {
+        "ms" => n / 1000.0,
+        "" | "s" => n,
+        "m" => n * 60.0,
+        _ => bail!("unknown unit {unit:?} in {s:?}, expected ms, s or m"),
+    };
+    Ok(Duration::try_from_secs_f64(secs)?)
+}
+
+// `Cancel::from_env` for a part of the calling binary, defaulting to TEST_BUDGET when that binary
+// is built for tests. A macro so `cfg!(test)` is the caller's and not this library's.
+#[macro_export]
+macro_rules! budget {
+    () => {
+        $crate::budget::Cancel::from_env(cfg!(test).then_some($crate::budget::TEST_BUDGET))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        for (s, want) in [
+            ("250ms", Duration::from_millis(250)),
+            ("10s", Duration::from_secs(10)),
+            ("1.5", Duration::from_millis(1500)),
+            ("2m", Duration::from_secs(120)),
+        ] {
+            assert_eq!(parse_duration(s).unwrap(), want, "{s}");
+        }
+        for bad in ["", "s", "10h", "-1s", "1.2.3"] {
+            assert!(parse_duration(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        assert_eq!(Cancel::never().check(), Ok(()));
+        assert_eq!(Cancel::after(Duration::from_secs(60)).check(), Ok(()));
+
+        let c = Cancel::after(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(1));
+        let e = c.check().unwrap_err();
+        assert_eq!(e.budget, Some(Duration::ZERO));
+        assert!(e.to_string().starts_with("TIMEOUT after "), "{e}");
+
+        let c = Cancel::never();
+        c.clone().cancel();
+        assert!(c.check().unwrap_err().to_string().ends_with("(cancelled)"));
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+// Arithmetic on numbers from the input. With the `checked` feature these report overflow as an
+// error naming the solver and the operation; without it they wrap, in debug builds too, so a
+// solver fed bad input never panics either way.
+pub const ENABLED: bool = cfg!(feature = "checked");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    pub solver: &'static str,
+    pub expr: String,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: overflow in {}", self.solver, self.expr)
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+pub trait Checked: Copy + fmt::Display {
+    const NAME: &'static str;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+}
+
+pub trait CheckedSigned: Checked {
+    fn checked_abs(self) -> Option<Self>;
+    fn wrapping_abs(self) -> Self;
+}
+
+macro_rules! impl_checked {
+    ($($t:ty),*) => {$(
+        impl Checked for $t {
+            const NAME: &'static str = stringify!($t);
+
+            #[inline(always)]
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+
+            #[inline(always)]
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_sub(self, rhs)
+            }
+
+            #[inline(always)]
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
+            }
+
+            #[inline(always)]
+            fn wrapping_add(self, rhs: Self) -> Self {
+                <$t>::wrapping_add(self, rhs)
+            }
+
+            #[inline(always)]
+            fn wrapping_sub(self, rhs: Self) -> Self {
+                <$t>::wrapping_sub(self, rhs)
+            }
+
+            #[inline(always)]
+            fn wrapping_mul(self, rhs: Self) -> Self {
+                <$t>::wrapping_mul(self, rhs)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_checked_signed {
+    ($($t:ty),*) => {$(
+        impl CheckedSigned for $t {
+            #[inline(always)]
+            fn checked_abs(self) -> Option<Self> {
+                <$t>::checked_abs(self)
+            }
+
+            #[inline(always)]
+            fn wrapping_abs(self) -> Self {
+                <$t>::wrapping_abs(self)
+            }
+        }
+    )*};
+}
+
+impl_checked!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+impl_checked_signed!(i8, i16, i32, i64, isize);
+
+fn overflow<T: Checked>(solver: &'static str, expr: String) -> Overflow {
+    Overflow {
+        solver,
+        expr: format!("{expr} ({})", T::NAME),
+    }
+}
+
+// Inlined even in debug builds, where these calls would otherwise dominate hot loops
+#[inline(always)]
+pub fn add<T: Checked>(solver: &'static str, a: T, b: T) -> Result<T, Overflow> {
+    if !ENABLED {
+        return Ok(a.wrapping_add(b));
+    }
+    a.checked_add(b)
+        .ok_or_else(|| overflow::<T>(solver, format!("{a} + {b}")))
+}
+
+#[inline(always)]
+pub fn sub<T: Checked>(solver: &'static str, a: T, b: T) -> Result<T, Overflow> {
+    if !ENABLED {
+        return Ok(a.wrapping_sub(b));
+    }
+    a.checked_sub(b)
+        .ok_or_else(|| overflow::<T>(solver, format!("{a} - {b}")))
+}
+
+#[inline(always)]
+pub fn mul<T: Checked>(solver: &'static str, a: T, b: T) -> Result<T, Overflow> {
+    if !ENABLED {
+        return Ok(a.wrapping_mul(b));
+    }
+    a.checked_mul(b)
+        .ok_or_else(|| overflow::<T>(solver, format!("{a} * {b}")))
+}
+
+#[inline(always)]
+pub fn abs<T: CheckedSigned>(solver: &'static str, a: T) -> Result<T, Overflow> {
+    if !ENABLED {
+        return Ok(a.wrapping_abs());
+    }
+    a.checked_abs()
+        .ok_or_else(|| overflow::<T>(solver, format!("|{a}|")))
+}
+
+// Sum starting from `zero`, stopping at the first overflow.
+pub fn sum<T: Checked>(
+    solver: &'static str,
+    zero: T,
+    nums: impl IntoIterator<Item = T>,
+) -> Result<T, Overflow> {
+    nums.into_iter()
+        .try_fold(zero, |acc, n| add(solver, acc, n))
+}
+
+// The name of the binary (or crate) using it, for `Overflow::solver`.
+#[macro_export]
+macro_rules! solver {
+    () => {
+        match option_env!("CARGO_BIN_NAME") {
+            Some(name) => name,
+            None => env!("CARGO_PKG_NAME"),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_range() {
+        assert_eq!(add("t", 200_u8, 55), Ok(255));
+        assert_eq!(sub("t", 5_i32, 7), Ok(-2));
+        assert_eq!(mul("t", 1 << 16, 1 << 15), Ok(1_u32 << 31));
+        assert_eq!(abs("t", -3_i64), Ok(3));
+        assert_eq!(sum("t", 0_u32, [1, 2, 3]), Ok(6));
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let e = add("day99", 200_u8, 56).unwrap_err();
+        assert_eq!(e.to_string(), "day99: overflow in 200 + 56 (u8)");
+        assert_eq!(
+            mul("t", u64::MAX, 2).unwrap_err().expr,
+            format!("{} * 2 (u64)", u64::MAX)
+        );
+        assert_eq!(abs("t", i32::MIN).unwrap_err().expr, "|-2147483648| (i32)");
+        assert_eq!(
+            sum("t", 0_u32, [u32::MAX, 0, 1]).unwrap_err().expr,
+            format!("{} + 1 (u32)", u32::MAX)
+        );
+        assert!(sub("t", 0_usize, 1).is_err());
+    }
+
+    #[cfg(not(feature = "checked"))]
+    #[test]
+    fn test_wraps() {
+        assert_eq!(add("t", 200_u8, 56), Ok(0));
+        assert_eq!(sub("t", 0_usize, 1), Ok(usize::MAX));
+        assert_eq!(abs("t", i32::MIN), Ok(i32::MIN));
+    }
+
+    #[test]
+    fn test_solver_name() {
+        assert_eq!(solver!(), "aoc-2022");
+    }
+}
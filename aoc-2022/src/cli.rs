@@ -0,0 +1,75 @@
+use std::env;
+
+// Value of `--name value` or `--name=value` in the process arguments.
+pub fn arg_value(name: &str) -> Option<String> {
+    find_value(env::args().skip(1), name)
+}
+
+pub fn has_flag(name: &str) -> bool {
+    env::args().skip(1).any(|a| a == name)
+}
+
+// A flag whose value is optional: None without it, Some(None) for a bare `--name`, whether last
+// or followed by another flag, and Some(Some(value)) like arg_value otherwise.
+pub fn optional_value(name: &str) -> Option<Option<String>> {
+    find_optional(env::args().skip(1), name)
+}
+
+fn find_value(mut args: impl Iterator<Item = String>, name: &str) -> Option<String> {
+    while let Some(a) = args.next() {
+        if a == name {
+            return args.next();
+        }
+        if let Some(v) = a.strip_prefix(name).and_then(|r| r.strip_prefix('=')) {
+            return Some(v.to_owned());
+        }
+    }
+    None
+}
+
+fn find_optional(args: impl Iterator<Item = String>, name: &str) -> Option<Option<String>> {
+    let mut args = args.peekable();
+    while let Some(a) = args.next() {
+        if a == name {
+            return Some(args.next_if(|v| !v.starts_with("--")));
+        }
+        if let Some(v) = a.strip_prefix(name).and_then(|r| r.strip_prefix('=')) {
+            return Some(Some(v.to_owned()));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_value() {
+        for (args, want) in [
+            (vec![], None),
+            (vec!["--render"], None),
+            (vec!["--render", "out.png"], Some("out.png")),
+            (vec!["--render=out.png"], Some("out.png")),
+            (vec!["--rendering=x", "--render", "a.ppm"], Some("a.ppm")),
+        ] {
+            let got = find_value(args.iter().map(|s| s.to_string()), "--render");
+            assert_eq!(got.as_deref(), want, "{args:?}")
+        }
+    }
+
+    #[test]
+    fn test_find_optional() {
+        for (args, want) in [
+            (vec![], None),
+            (vec!["--boards"], None),
+            (vec!["--board"], Some(None)),
+            (vec!["--board", "--animate"], Some(None)),
+            (vec!["--board", "10"], Some(Some("10"))),
+            (vec!["--board=10"], Some(Some("10"))),
+        ] {
+            let got = find_optional(args.iter().map(|s| s.to_string()), "--board");
+            assert_eq!(got.as_ref().map(Option::as_deref), want, "{args:?}")
+        }
+    }
+}
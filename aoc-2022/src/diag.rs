@@ -0,0 +1,207 @@
+use std::{fmt, str::FromStr};
+
+// A parse error anchored to a location in the input, rendered with a caret under the culprit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub name: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    offset: usize,
+    width: usize,
+    source_line: String,
+}
+
+impl Diagnostic {
+    // `span` must be a sub-slice of `input`; anything else is reported at the start of `input`.
+    pub fn new(input: &str, span: &str, message: impl fmt::Display) -> Self {
+        let offset = offset_in(input, span).unwrap_or(0);
+        Self::at(input, offset, span.chars().count(), message)
+    }
+
+    // An offset past the end, or inside a character, is moved back to the nearest character start.
+    pub fn at(input: &str, offset: usize, width: usize, message: impl fmt::Display) -> Self {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1
+        }
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+        Self {
+            name: "input".to_owned(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.to_string(),
+            offset,
+            width,
+            source_line: input[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_owned(),
+        }
+    }
+
+    // Errors from the no_std core only know a byte offset into `input`.
+    pub fn from_core(input: &str, e: aoc_2022_core::Error) -> Self {
+        Self::at(input, e.offset, e.len, e.kind)
+    }
+
+    // For input read a line at a time, where the diagnostic was made from `line` alone.
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    // Re-anchors a diagnostic produced while parsing `part`, a sub-slice of `input`.
+    pub fn within(self, input: &str, part: &str) -> Self {
+        match offset_in(input, part) {
+            Some(base) => Self {
+                name: self.name,
+                ..Self::at(input, base + self.offset, self.width, self.message)
+            },
+            None => self,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let num = self.line.to_string();
+        let pad = " ".repeat(num.len());
+        let caret_pad = " ".repeat(self.column - 1);
+        let carets = "^".repeat(self.width.max(1));
+        writeln!(
+            f,
+            "{}:{}:{}: {}",
+            self.name, self.line, self.column, self.message
+        )?;
+        writeln!(f, "{pad} |")?;
+        writeln!(f, "{num} | {}", self.source_line)?;
+        write!(f, "{pad} | {caret_pad}{carets}")
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+fn offset_in(input: &str, span: &str) -> Option<usize> {
+    let start = input.as_ptr() as usize;
+    let pos = span.as_ptr() as usize;
+    (start..=start + input.len())
+        .contains(&pos)
+        .then_some(pos - start)
+}
+
+// Parses `span` (a sub-slice of `input`) with its FromStr, reporting failures at `span`.
+pub fn parse_span<T>(input: &str, span: &str) -> Result<T, Diagnostic>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    span.parse()
+        .map_err(|e| Diagnostic::new(input, span, format!("{span:?}: {e}")))
+}
+
+// Parses each line with a FromStr that reports its own diagnostics relative to the line.
+pub fn parse_lines<T>(input: &str) -> Result<Vec<T>, Diagnostic>
+where
+    T: FromStr<Err = Diagnostic>,
+{
+    input
+        .lines()
+        .map(|l| l.parse().map_err(|d: Diagnostic| d.within(input, l)))
+        .collect()
+}
+
+// Names any diagnostic in `err`'s chain, for errors that have already left the parser.
+pub fn name_input(mut err: anyhow::Error, name: &str) -> anyhow::Error {
+    if let Some(d) = err.downcast_mut::<Diagnostic>() {
+        d.name = name.to_owned();
+    }
+    err
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let input = "first\nsecond line\r\nthird";
+        let second = input.lines().nth(1).unwrap();
+        let d = Diagnostic::new(input, &second[7..], "bad");
+        assert_eq!((d.line, d.column), (2, 8));
+        assert_eq!(
+            d.named("x.txt").to_string(),
+            "x.txt:2:8: bad\n  |\n2 | second line\n  |        ^^^^"
+        );
+
+        let d = Diagnostic::new(input, "not in input", "bad");
+        assert_eq!((d.line, d.column), (1, 1));
+    }
+
+    #[test]
+    fn test_at_char_boundary() {
+        let input = "ab
+é😀x";
+        // Inside 'é', then inside the emoji, then past the end
+        for (offset, column) in [(4, 1), (7, 2), (99, 4)] {
+            let d = Diagnostic::at(input, offset, 1, "bad");
+            assert_eq!((d.line, d.column), (2, column), "{offset}");
+        }
+    }
+
+    #[test]
+    fn test_within() {
+        let input = "a\nb c";
+        let line = input.lines().nth(1).unwrap();
+        let d = Diagnostic::new(line, &line[2..], "bad").within(input, line);
+        assert_eq!((d.line, d.column), (2, 3));
+        assert_eq!(d.source_line, "b c");
+    }
+
+    #[test]
+    fn test_on_line() {
+        let line = "12 x";
+        let d = Diagnostic::new(line, &line[3..], "bad").on_line(40);
+        assert_eq!(d.to_string(), "input:40:4: bad\n   |\n40 | 12 x\n   |    ^");
+    }
+
+    #[test]
+    fn test_from_core() {
+        let input = "1-2,3-4\n1-2,3-x";
+        let e = aoc_2022_core::day04::solve(input.as_bytes()).unwrap_err();
+        let d = Diagnostic::from_core(input, e);
+        assert_eq!((d.line, d.column), (2, 7));
+        assert_eq!(d.message, "expected a digit");
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        #[derive(Debug)]
+        struct N(u8);
+        impl FromStr for N {
+            type Err = Diagnostic;
+
+            fn from_str(s: &str) -> Result<Self, Diagnostic> {
+                let (_, num) = s
+                    .split_once(' ')
+                    .ok_or_else(|| Diagnostic::new(s, s, "no space"))?;
+                Ok(N(parse_span(s, num)?))
+            }
+        }
+
+        let nums: Vec<_> = parse_lines::<N>("x 1\nx 2").unwrap();
+        assert_eq!(nums.iter().map(|n| n.0).collect::<Vec<_>>(), [1, 2]);
+        let d = parse_lines::<N>("x 1\nx 2\nx 300").unwrap_err();
+        assert_eq!((d.line, d.column), (3, 3));
+        let d = parse_lines::<N>("x 1\nx2").unwrap_err();
+        assert_eq!((d.line, d.column, d.message.as_str()), (2, 1, "no space"));
+    }
+}
@@ -0,0 +1,42 @@
+// Day 5's drawing of stacks of crates, as rows top first, then the stack numbers. `columns` are
+// the stacks, bottom crate first. Crates and numbers are centred in cells as wide as the widest of
+// either, and rows are padded to the full width.
+pub fn crates<S: AsRef<str>>(columns: &[Vec<S>]) -> Vec<String> {
+    let n = columns.len();
+    let widest = columns
+        .iter()
+        .flatten()
+        .map(|l| l.as_ref().chars().count() + 2);
+    let w = widest.chain([3, n.to_string().len()]).max().unwrap_or(3);
+    let height = columns.iter().map(Vec::len).max().unwrap_or(0);
+    let mut lines = Vec::with_capacity(height + 1);
+    for y in (0..height).rev() {
+        let row: Vec<_> = columns
+            .iter()
+            .map(|c| match c.get(y) {
+                Some(label) => format!("{:^w$}", format!("[{}]", label.as_ref())),
+                None => " ".repeat(w),
+            })
+            .collect();
+        lines.push(row.join(" "));
+    }
+    let labels: Vec<_> = (1..=n).map(|i| format!("{i:^w$}")).collect();
+    lines.push(labels.join(" "));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crates() {
+        assert_eq!(
+            crates(&[vec!["Z", "N"], vec!["M"]]),
+            ["[N]    ", "[Z] [M]", " 1   2 "]
+        );
+        // Wide labels widen every cell
+        assert_eq!(crates(&[vec!["ab"], vec![]]), ["[ab]     ", " 1    2  "]);
+        assert_eq!(crates::<&str>(&[]), [""]);
+    }
+}
@@ -0,0 +1,353 @@
+use anyhow::{anyhow, bail, ensure, Result};
+
+// A filesystem kept in one Vec, with directories caching the total size of everything under
+// them. Nodes are never freed, so a NodeId stays valid (if detached) after `remove`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Dir(Vec<NodeId>),
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    kind: Kind,
+    // For files, the file's size. For directories, the sum of everything below.
+    size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FsTree {
+    pub fn new() -> Self {
+        let root = Node {
+            name: String::new(),
+            parent: None,
+            kind: Kind::Dir(Vec::new()),
+            size: 0,
+        };
+        Self { nodes: vec![root] }
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.nodes[id.0].name
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn is_dir(&self, id: NodeId) -> bool {
+        matches!(self.nodes[id.0].kind, Kind::Dir(_))
+    }
+
+    // Total size, including everything below a directory. Cached, so this is O(1).
+    pub fn size(&self, id: NodeId) -> usize {
+        self.nodes[id.0].size
+    }
+
+    // Empty for files.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        match &self.nodes[id.0].kind {
+            Kind::Dir(c) => c,
+            Kind::File => &[],
+        }
+    }
+
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.children(dir)
+            .iter()
+            .copied()
+            .find(|c| self.name(*c) == name)
+    }
+
+    // Returns the existing directory if there is one.
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> Result<NodeId> {
+        match self.child(parent, name) {
+            Some(id) if self.is_dir(id) => Ok(id),
+            Some(_) => bail!("{:?} is a file", self.path_of(parent, name)),
+            None => self.insert(parent, name, Kind::Dir(Vec::new()), 0),
+        }
+    }
+
+    // Adding a file that's already there updates its size.
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: usize) -> Result<NodeId> {
+        match self.child(parent, name) {
+            Some(id) if !self.is_dir(id) => {
+                self.set_size(id, size)?;
+                Ok(id)
+            }
+            Some(_) => bail!("{:?} is a directory", self.path_of(parent, name)),
+            None => self.insert(parent, name, Kind::File, size),
+        }
+    }
+
+    fn insert(&mut self, parent: NodeId, name: &str, kind: Kind, size: usize) -> Result<NodeId> {
+        ensure!(!name.is_empty() && !name.contains('/'), "bad name {name:?}");
+        let id = NodeId(self.nodes.len());
+        ensure!(
+            self.is_dir(parent),
+            "{:?} isn't a directory",
+            self.path(parent)
+        );
+        self.adjust(parent, 0, size)?;
+        if let Kind::Dir(children) = &mut self.nodes[parent.0].kind {
+            children.push(id);
+        }
+        self.nodes.push(Node {
+            name: name.to_owned(),
+            parent: Some(parent),
+            kind,
+            size,
+        });
+        Ok(id)
+    }
+
+    pub fn set_size(&mut self, file: NodeId, size: usize) -> Result<()> {
+        ensure!(
+            !self.is_dir(file),
+            "can't set the size of directory {:?}",
+            self.path(file)
+        );
+        self.adjust(file, self.size(file), size)
+    }
+
+    // Detaches `id` and everything below it, taking its size off its ancestors.
+    pub fn remove(&mut self, id: NodeId) -> Result<()> {
+        let parent = self
+            .parent(id)
+            .ok_or_else(|| anyhow!("can't remove the root"))?;
+        self.adjust(parent, self.size(id), 0)?;
+        if let Kind::Dir(children) = &mut self.nodes[parent.0].kind {
+            children.retain(|c| *c != id);
+        }
+        self.nodes[id.0].parent = None;
+        Ok(())
+    }
+
+    // Swaps `old` for `new` in the size of `id` and every directory above it, so totals never need
+    // a full recompute. Every size includes `old` already, so only adding can overflow, and then
+    // nothing changes.
+    fn adjust(&mut self, id: NodeId, old: usize, new: usize) -> Result<()> {
+        let mut sizes = Vec::new();
+        let mut cur = Some(id);
+        while let Some(c) = cur {
+            let size = (self.size(c) - old)
+                .checked_add(new)
+                .ok_or_else(|| anyhow!("size of {:?} overflows", self.path(c)))?;
+            sizes.push((c, size));
+            cur = self.parent(c);
+        }
+        for (c, size) in sizes {
+            self.nodes[c.0].size = size;
+        }
+        Ok(())
+    }
+
+    // Absolute ("/a/b") or relative to the root ("a/b"). ".." goes up, stopping at the root.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        let mut cur = self.root();
+        for part in path.split('/').filter(|p| !p.is_empty() && *p != ".") {
+            cur = match part {
+                ".." => self.parent(cur).unwrap_or(cur),
+                _ => self.child(cur, part)?,
+            };
+        }
+        Some(cur)
+    }
+
+    pub fn path(&self, id: NodeId) -> String {
+        let mut parts = Vec::new();
+        let mut cur = Some(id);
+        while let Some(c) = cur {
+            parts.push(self.name(c));
+            cur = self.parent(c);
+        }
+        if parts.len() == 1 {
+            return "/".to_owned();
+        }
+        parts.reverse();
+        parts.join("/")
+    }
+
+    fn path_of(&self, parent: NodeId, name: &str) -> String {
+        let p = self.path(parent);
+        if p.ends_with('/') {
+            p + name
+        } else {
+            p + "/" + name
+        }
+    }
+
+    // Parents before children, children in insertion order.
+    pub fn pre_order(&self, from: NodeId) -> PreOrder<'_> {
+        PreOrder {
+            tree: self,
+            stack: vec![from],
+        }
+    }
+
+    // Children before parents, so each directory comes after everything in it.
+    pub fn post_order(&self, from: NodeId) -> PostOrder<'_> {
+        PostOrder {
+            tree: self,
+            stack: vec![(from, false)],
+        }
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.pre_order(self.root()).filter(|id| self.is_dir(*id))
+    }
+}
+
+pub struct PreOrder<'a> {
+    tree: &'a FsTree,
+    stack: Vec<NodeId>,
+}
+
+impl Iterator for PreOrder<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.stack.pop()?;
+        self.stack
+            .extend(self.tree.children(id).iter().rev().copied());
+        Some(id)
+    }
+}
+
+pub struct PostOrder<'a> {
+    tree: &'a FsTree,
+    // The flag is set once a node's children have been pushed
+    stack: Vec<(NodeId, bool)>,
+}
+
+impl Iterator for PostOrder<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        loop {
+            let (id, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(id);
+            }
+            self.stack.push((id, true));
+            self.stack
+                .extend(self.tree.children(id).iter().rev().map(|c| (*c, false)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // /a/x (10), /a/b/y (5), /z (1)
+    fn sample() -> FsTree {
+        let mut t = FsTree::new();
+        let a = t.add_dir(t.root(), "a").unwrap();
+        t.add_file(a, "x", 10).unwrap();
+        let b = t.add_dir(a, "b").unwrap();
+        t.add_file(b, "y", 5).unwrap();
+        t.add_file(t.root(), "z", 1).unwrap();
+        t
+    }
+
+    fn paths(t: &FsTree, ids: impl Iterator<Item = NodeId>) -> Vec<String> {
+        ids.map(|id| t.path(id)).collect()
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut t = sample();
+        let sizes = |t: &FsTree| ["/", "/a", "/a/b"].map(|p| t.size(t.lookup(p).unwrap()));
+        assert_eq!(sizes(&t), [16, 15, 5]);
+
+        let y = t.lookup("/a/b/y").unwrap();
+        t.set_size(y, 50).unwrap();
+        assert_eq!(sizes(&t), [61, 60, 50]);
+        // Re-adding a file updates it
+        t.add_file(t.lookup("a").unwrap(), "x", 0).unwrap();
+        assert_eq!(sizes(&t), [51, 50, 50]);
+
+        t.remove(t.lookup("/a/b").unwrap()).unwrap();
+        assert_eq!(t.size(t.root()), 1);
+        assert_eq!(t.lookup("/a/b"), None);
+        assert!(t.remove(t.root()).is_err());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let t = sample();
+        let y = t.lookup("/a/b/y").unwrap();
+        assert_eq!(t.path(y), "/a/b/y");
+        assert_eq!(t.name(y), "y");
+        assert_eq!(t.lookup("a/b/../../z"), t.lookup("/z"));
+        assert_eq!(t.lookup("/"), Some(t.root()));
+        assert_eq!(t.path(t.root()), "/");
+        assert_eq!(t.lookup("/a/q"), None);
+        assert_eq!(t.lookup("/z/q"), None);
+    }
+
+    // Totals that don't fit are errors that leave every size as it was
+    #[test]
+    fn test_overflow() {
+        let mut t = FsTree::new();
+        let a = t.add_dir(t.root(), "a").unwrap();
+        let big = t.add_file(t.root(), "big", usize::MAX - 1).unwrap();
+        assert!(t.add_file(a, "more", 2).is_err());
+        assert_eq!(t.child(a, "more"), None);
+        assert_eq!(t.size(t.root()), usize::MAX - 1);
+        t.set_size(big, usize::MAX).unwrap();
+        assert!(t.add_file(a, "one", 1).is_err());
+        assert!(t.set_size(big, 0).is_ok());
+        assert_eq!(t.size(t.root()), 0);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut t = sample();
+        let a = t.lookup("/a").unwrap();
+        assert_eq!(t.add_dir(t.root(), "a").unwrap(), a);
+        assert!(t.add_dir(a, "x").is_err());
+        assert!(t.add_file(t.root(), "a", 1).is_err());
+        assert!(t.add_file(t.lookup("/z").unwrap(), "q", 1).is_err());
+        assert!(t.set_size(a, 1).is_err());
+        assert!(t.add_dir(a, "").is_err());
+    }
+
+    #[test]
+    fn test_order() {
+        let t = sample();
+        assert_eq!(
+            paths(&t, t.pre_order(t.root())),
+            ["/", "/a", "/a/x", "/a/b", "/a/b/y", "/z"]
+        );
+        assert_eq!(
+            paths(&t, t.post_order(t.root())),
+            ["/a/x", "/a/b/y", "/a/b", "/a", "/z", "/"]
+        );
+        assert_eq!(
+            paths(&t, t.post_order(t.lookup("/a/b").unwrap())),
+            ["/a/b/y", "/a/b"]
+        );
+        assert_eq!(paths(&t, t.dirs()), ["/", "/a", "/a/b"]);
+    }
+}
@@ -0,0 +1,98 @@
+use std::panic::{self, RefUnwindSafe};
+
+use crate::rng::Lcg;
+
+// Inputs broken the ways real ones get broken: empty, cut short, and with a character dropped,
+// repeated or swapped for another. The choices are pseudo-random but fixed, so a failure always
+// comes back the same.
+pub fn broken_inputs(input: &str) -> Vec<String> {
+    const TRUNCATIONS: usize = 40;
+    const MUTATIONS: usize = 120;
+    const REPLACEMENTS: [char; 10] = ['0', '9', ' ', '\n', 'a', 'z', '-', ',', '[', ']'];
+
+    let bounds: Vec<usize> = input.char_indices().map(|(i, _)| i).collect();
+    let mut out = vec![String::new()];
+    if bounds.is_empty() {
+        return out;
+    }
+    let step = bounds.len().div_ceil(TRUNCATIONS);
+    out.extend(bounds.iter().step_by(step).map(|&i| input[..i].to_owned()));
+    // Just before and after each line break, where parsers tend to make assumptions
+    for (i, _) in input.match_indices('\n') {
+        out.push(input[..i].to_owned());
+        out.push(input[..=i].to_owned());
+    }
+
+    let mut rng = Lcg::new(0x2022);
+    for _ in 0..MUTATIONS {
+        let at = bounds[rng.below(bounds.len())];
+        let c = input[at..].chars().next().unwrap();
+        let (before, after) = (&input[..at], &input[at + c.len_utf8()..]);
+        out.push(match rng.below(3) {
+            0 => format!("{before}{after}"),
+            1 => format!("{before}{c}{c}{after}"),
+            _ => format!(
+                "{before}{}{after}",
+                REPLACEMENTS[rng.below(REPLACEMENTS.len())]
+            ),
+        });
+    }
+    // Swapping a character for itself gives back the original
+    out.retain(|i| i != input);
+    out.sort();
+    out.dedup();
+    out
+}
+
+// Runs `solve` on every broken version of `input`, failing with the ones that made it panic.
+// Errors are fine, that's what they're for; overflow should be one too, see src/checked.rs.
+pub fn assert_no_panics<T>(input: &str, solve: impl Fn(&str) -> T + RefUnwindSafe) {
+    let panicked: Vec<String> = broken_inputs(input)
+        .into_iter()
+        .filter(|i| panic::catch_unwind(|| solve(i)).is_err())
+        .collect();
+    assert!(
+        panicked.is_empty(),
+        "{} inputs panicked, including {:?}",
+        panicked.len(),
+        &panicked[..panicked.len().min(3)]
+    );
+}
+
+// The no-panics test for a binary, which can't share one test with the others since its solver is
+// private to it: `test_no_panics!(include_str!("../../data/example/day01.txt"), solve);` in its
+// tests module.
+#[macro_export]
+macro_rules! test_no_panics {
+    ($input:expr, $solve:expr) => {
+        #[test]
+        fn test_no_panics() {
+            $crate::fuzz::assert_no_panics($input, $solve);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broken_inputs() {
+        let inputs = broken_inputs("12\n34\n");
+        assert_eq!(inputs, broken_inputs("12\n34\n"));
+        for want in ["", "1", "12", "12\n", "2\n34\n", "112\n34\n"] {
+            assert!(inputs.iter().any(|i| i == want), "{want:?} in {inputs:?}");
+        }
+        assert!(!inputs.iter().any(|i| i == "12\n34\n"));
+        assert_eq!(broken_inputs(""), [""]);
+        // Cuts only on char boundaries
+        assert!(broken_inputs("é😀ü").iter().all(|i| i.chars().count() <= 4));
+    }
+
+    #[test]
+    fn test_assert_no_panics() {
+        assert_no_panics("123", |s| s.len());
+        let r = panic::catch_unwind(|| assert_no_panics("123", |s| s.as_bytes()[2]));
+        assert!(r.is_err());
+    }
+}
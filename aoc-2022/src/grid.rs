@@ -0,0 +1,79 @@
+use anyhow::{ensure, Result};
+
+// A rectangular grid stored row-major. `x` is the column and `y` is the row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    rows: Vec<Vec<T>>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self> {
+        if let Some(first) = rows.first() {
+            let width = first.len();
+            for (i, r) in rows.iter().enumerate() {
+                ensure!(
+                    r.len() == width,
+                    "row {i} has width {}, expected {width}",
+                    r.len()
+                );
+            }
+        }
+        Ok(Self { rows })
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.rows.get(y).and_then(|r| r.get(x))
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.rows.get_mut(y).and_then(|r| r.get_mut(x))
+    }
+
+    pub fn rows(&self) -> &[Vec<T>] {
+        &self.rows
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<T>> {
+        self.rows
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            rows: self
+                .rows
+                .iter()
+                .map(|r| r.iter().map(&mut f).collect())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_rows() {
+        let g = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        assert_eq!((g.width(), g.height()), (3, 2));
+        assert_eq!(g.get(2, 1), Some(&6));
+        assert_eq!(g.get(3, 0), None);
+
+        assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_err());
+
+        let empty: Grid<u8> = Grid::from_rows(vec![]).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -1,2 +1,19 @@
 // see bin
+pub mod anim;
+pub mod budget;
+pub mod checked;
+pub mod cli;
+pub mod diag;
+pub mod drawing;
+pub mod fs_tree;
+pub mod fuzz;
+pub mod grid;
+pub mod lint;
 pub mod nom_util;
+pub mod num;
+pub mod par;
+pub mod render;
+pub mod rng;
+pub mod rope;
+pub mod stats;
+pub mod trace;
@@ -0,0 +1,111 @@
+use std::fs;
+
+use anyhow::{ensure, Context, Result};
+
+use crate::{cli::arg_value, diag::Diagnostic};
+
+// Something a solution takes for granted about its input rather than checking while it solves.
+// `check` returns everywhere the input breaks it, so nothing means it holds.
+pub struct Assumption {
+    pub what: &'static str,
+    pub check: fn(&str) -> Vec<Diagnostic>,
+}
+
+// Broken assumptions are shown with this many locations, the rest are counted.
+const SHOWN: usize = 5;
+
+// Each assumption with where the input breaks it, in the order they were declared.
+pub fn lint<'a>(
+    input: &str,
+    assumptions: &'a [Assumption],
+) -> Vec<(&'a Assumption, Vec<Diagnostic>)> {
+    assumptions.iter().map(|a| (a, (a.check)(input))).collect()
+}
+
+pub fn report(name: &str, results: &[(&Assumption, Vec<Diagnostic>)]) -> String {
+    if results.is_empty() {
+        return format!("{name}: no assumptions declared for this solution\n");
+    }
+    let mut out = String::new();
+    for (a, found) in results {
+        if found.is_empty() {
+            out += &format!("ok    {}\n", a.what);
+            continue;
+        }
+        out += &format!("FAIL  {} ({} found)\n", a.what, found.len());
+        for d in found.iter().take(SHOWN) {
+            out += &format!("{}\n", d.clone().named(name));
+        }
+        if found.len() > SHOWN {
+            out += &format!("...and {} more\n", found.len() - SHOWN);
+        }
+    }
+    out
+}
+
+// Handles `--lint <input>`, checking the input against `assumptions` instead of solving. Returns
+// whether it did, in which case main should stop. Only days that declare assumptions call it, and
+// `aoc lint` answers for the others without running them.
+pub fn lint_arg(assumptions: &[Assumption]) -> Result<bool> {
+    let Some(path) = arg_value("--lint") else {
+        return Ok(false);
+    };
+    let input = fs::read_to_string(&path).with_context(|| format!("reading {path}"))?;
+    let results = lint(&input, assumptions);
+    print!("{}", report(&path, &results));
+    let broken = results
+        .iter()
+        .filter(|(_, found)| !found.is_empty())
+        .count();
+    ensure!(
+        broken == 0,
+        "{broken} of {} assumptions don't hold for {path}",
+        results.len()
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_x(input: &str) -> Vec<Diagnostic> {
+        input
+            .match_indices('x')
+            .map(|(i, _)| Diagnostic::at(input, i, 1, "an x"))
+            .collect()
+    }
+
+    const NO_X: [Assumption; 2] = [
+        Assumption {
+            what: "no x",
+            check: no_x,
+        },
+        Assumption {
+            what: "anything goes",
+            check: |_| vec![],
+        },
+    ];
+
+    #[test]
+    fn test_report() {
+        assert_eq!(
+            report("in.txt", &lint("ab\ncd", &NO_X)),
+            "ok    no x\nok    anything goes\n"
+        );
+        assert_eq!(
+            report("in.txt", &lint("ab\nxd", &NO_X)),
+            "FAIL  no x (1 found)\nin.txt:2:1: an x\n  |\n2 | xd\n  | ^\nok    anything goes\n"
+        );
+        let many = report("in.txt", &lint("xxxxxxxx", &NO_X));
+        assert!(many.starts_with("FAIL  no x (8 found)\n"), "{many}");
+        assert!(
+            many.ends_with("...and 3 more\nok    anything goes\n"),
+            "{many}"
+        );
+        assert_eq!(
+            report("in.txt", &lint("", &[])),
+            "in.txt: no assumptions declared for this solution\n"
+        );
+    }
+}
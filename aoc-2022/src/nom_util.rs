@@ -1,15 +1,204 @@
-use anyhow::{anyhow, Result};
-use nom::{combinator::all_consuming, error::convert_error, Finish};
+use std::str::FromStr;
+
+use anyhow::Result;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, line_ending, satisfy, space0},
+    combinator::{all_consuming, eof, map_opt, map_res, opt, recognize, value},
+    error::{ErrorKind, VerboseErrorKind},
+    multi::{many0, many1, separated_list0, separated_list1},
+    sequence::{pair, preceded, terminated, tuple},
+    Finish, Parser,
+};
+
+use crate::{diag::Diagnostic, grid::Grid};
 
 pub type IError<'a> = nom::error::VerboseError<&'a str>;
 pub type IResult<'a, T> = nom::IResult<&'a str, T, IError<'a>>;
 
-pub fn run_parser<'a, T>(parser: fn(&'a str) -> IResult<T>, input: &'a str) -> Result<T> {
-    all_consuming(parser)(input)
+// Runs `parser` over all of `input`, allowing trailing line endings (LF or CRLF).
+#[tracing::instrument(name = "parse", skip_all)]
+pub fn run_parser<'a, T>(parser: fn(&'a str) -> IResult<'a, T>, input: &'a str) -> Result<T> {
+    all_consuming(terminated(parser, many0(line_ending)))(input)
         .finish()
         .map(|(_, out)| out)
-        .map_err(|e| {
-            let msg = convert_error(input, e);
-            anyhow!("Parse error: {msg}")
-        })
+        .map_err(|e| diagnose(input, e).into())
+}
+
+// Reports the innermost failure, followed by any `context` it was nested in.
+pub fn diagnose<'a>(input: &'a str, e: IError<'a>) -> Diagnostic {
+    let Some((rest, kind)) = e.errors.first() else {
+        return Diagnostic::at(input, 0, 0, "parse error");
+    };
+    let found = match rest.chars().next() {
+        Some(c) => format!("{c:?}"),
+        None => "end of input".to_owned(),
+    };
+    let mut msg = match kind {
+        VerboseErrorKind::Char(c) => format!("expected {c:?}, found {found}"),
+        VerboseErrorKind::Context(ctx) => format!("invalid {ctx}"),
+        VerboseErrorKind::Nom(ErrorKind::Eof) => format!("expected end of input, found {found}"),
+        VerboseErrorKind::Nom(ErrorKind::MapRes | ErrorKind::MapOpt) => "invalid value".to_owned(),
+        VerboseErrorKind::Nom(k) => format!("expected {}, found {found}", k.description()),
+    };
+    for (_, kind) in e.errors.iter().skip(1) {
+        if let VerboseErrorKind::Context(ctx) = kind {
+            msg += &format!(", in {ctx}");
+        }
+    }
+    Diagnostic::at(
+        input,
+        input.len() - rest.len(),
+        1,
+        format!("parse error: {msg}"),
+    )
+}
+
+// Unsigned integer. Overflow is a parse error rather than a wrap.
+pub fn uint<T: FromStr>(input: &str) -> IResult<'_, T> {
+    map_res(digit1, str::parse)(input)
+}
+
+// Integer with an optional leading '-'. Overflow is a parse error rather than a wrap.
+pub fn int<T: FromStr>(input: &str) -> IResult<'_, T> {
+    map_res(recognize(pair(opt(char('-')), digit1)), str::parse)(input)
+}
+
+pub fn line_end_or_eof(input: &str) -> IResult<'_, &str> {
+    alt((line_ending, eof))(input)
+}
+
+pub fn blank_line(input: &str) -> IResult<'_, ()> {
+    value((), pair(line_ending, line_ending))(input)
+}
+
+// One or more `f`, one per line.
+pub fn lines<'a, O, F>(f: F) -> impl FnMut(&'a str) -> IResult<'a, Vec<O>>
+where
+    F: Parser<&'a str, O, IError<'a>>,
+{
+    separated_list1(line_ending, f)
+}
+
+// Zero or more `f`, separated by blank lines.
+pub fn blocks<'a, O, F>(f: F) -> impl FnMut(&'a str) -> IResult<'a, Vec<O>>
+where
+    F: Parser<&'a str, O, IError<'a>>,
+{
+    separated_list0(blank_line, f)
+}
+
+// A line like "  Label: value". Leading indentation and space after the colon are skipped.
+pub fn keyed<'a, O, F>(key: &'static str, f: F) -> impl FnMut(&'a str) -> IResult<'a, O>
+where
+    F: Parser<&'a str, O, IError<'a>>,
+{
+    preceded(tuple((space0, tag(key), char(':'), space0)), f)
+}
+
+// A rectangular grid of characters. `cell` rejects a character by returning None.
+pub fn grid<'a, T, F>(cell: F) -> impl FnMut(&'a str) -> IResult<'a, Grid<T>>
+where
+    F: Fn(char) -> Option<T> + Copy,
+{
+    map_res(
+        separated_list1(
+            line_ending,
+            many1(map_opt(satisfy(|c| c != '\r' && c != '\n'), cell)),
+        ),
+        Grid::from_rows,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uint() {
+        assert_eq!(run_parser(uint::<u8>, "255").unwrap(), 255);
+        assert!(run_parser(uint::<u8>, "256").is_err());
+        assert!(run_parser(uint::<u8>, "-1").is_err());
+        assert_eq!(
+            run_parser(uint::<u64>, "18446744073709551615\n").unwrap(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_diagnose() {
+        fn num_pair(input: &str) -> IResult<'_, (u8, u8)> {
+            nom::sequence::separated_pair(uint, char(','), uint)(input)
+        }
+        fn num_pairs(input: &str) -> IResult<'_, Vec<(u8, u8)>> {
+            lines(num_pair)(input)
+        }
+        for (input, want_pos, want_msg) in [
+            ("1;2", (1, 2), "expected ',', found ';'"),
+            ("1,999", (1, 3), "invalid value"),
+        ] {
+            let err = run_parser(num_pair, input).unwrap_err();
+            let d = err.downcast_ref::<Diagnostic>().unwrap();
+            assert_eq!((d.line, d.column), want_pos, "{d}");
+            assert_eq!(d.message, format!("parse error: {want_msg}"));
+        }
+
+        // The list stops at the bad line, so that's where the leftover input starts
+        let err = run_parser(num_pairs, "1,2\n3;4").unwrap_err();
+        let d = err.downcast_ref::<Diagnostic>().unwrap();
+        assert_eq!((d.line, d.column), (2, 1), "{d}");
+    }
+
+    #[test]
+    fn test_int() {
+        for (input, want) in [
+            ("0", 0),
+            ("-0", 0),
+            ("17", 17),
+            ("-17", -17),
+            ("-128", -128),
+        ] {
+            assert_eq!(run_parser(int::<i8>, input).unwrap(), want, "{input:?}")
+        }
+        assert!(run_parser(int::<i8>, "128").is_err());
+        assert!(run_parser(int::<i8>, "-129").is_err());
+        assert!(run_parser(int::<i8>, "--1").is_err());
+    }
+
+    #[test]
+    fn test_blocks() {
+        fn nums(input: &str) -> IResult<'_, Vec<Vec<u32>>> {
+            blocks(lines(uint))(input)
+        }
+        for (input, want) in [
+            ("", vec![]),
+            ("1", vec![vec![1]]),
+            ("1\n2\n\n3", vec![vec![1, 2], vec![3]]),
+            ("1\r\n2\r\n\r\n3\r\n", vec![vec![1, 2], vec![3]]),
+        ] {
+            assert_eq!(run_parser(nums, input).unwrap(), want, "{input:?}")
+        }
+    }
+
+    #[test]
+    fn test_keyed() {
+        fn item(input: &str) -> IResult<'_, u32> {
+            keyed("Test", preceded(tag("divisible by "), uint))(input)
+        }
+        assert_eq!(run_parser(item, "  Test: divisible by 7").unwrap(), 7);
+        assert!(run_parser(item, "  Tset: divisible by 7").is_err());
+    }
+
+    #[test]
+    fn test_grid() {
+        fn digits(input: &str) -> IResult<'_, Grid<u32>> {
+            grid(|c| c.to_digit(10))(input)
+        }
+        let g = run_parser(digits, "12\r\n34\r\n").unwrap();
+        assert_eq!(g.rows(), &[vec![1, 2], vec![3, 4]]);
+
+        assert!(run_parser(digits, "12\n3").is_err());
+        assert!(run_parser(digits, "12\n3x").is_err());
+    }
 }
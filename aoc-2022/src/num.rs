@@ -0,0 +1,167 @@
+// Number theory helpers. Everything is u64 on the outside, with wider types inside where
+// intermediate products could overflow.
+
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// lcm(0, x) is 0, matching the usual convention that 0 is a multiple of everything.
+pub fn checked_lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// gcd of no numbers is 0, the identity for gcd.
+pub fn gcd_all(nums: impl IntoIterator<Item = u64>) -> u64 {
+    nums.into_iter().fold(0, gcd)
+}
+
+// lcm of no numbers is 1, the identity for lcm. None if the result doesn't fit in u64.
+pub fn checked_lcm_all(nums: impl IntoIterator<Item = u64>) -> Option<u64> {
+    nums.into_iter().try_fold(1, checked_lcm)
+}
+
+// `base` to the power `exp`, modulo `m`.
+pub fn pow_mod(base: u64, mut exp: u64, m: u64) -> u64 {
+    assert!(m > 0, "modulus must be positive");
+    let m = m as u128;
+    let mut base = base as u128 % m;
+    let mut acc = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            acc = acc * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    acc as u64
+}
+
+// Returns (g, x, y) with a*x + b*y = g = gcd(a, b).
+pub fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = ext_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+// x with a*x = 1 (mod m), if a and m are coprime.
+pub fn inv_mod(a: u64, m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+    let (g, x, _) = ext_gcd(a as i128, m as i128);
+    (g == 1).then(|| x.rem_euclid(m as i128) as u64)
+}
+
+// Solves x = r (mod m) for every (r, m), returning (x, lcm of the moduli) with x the smallest
+// non-negative solution. Moduli needn't be coprime. None if the congruences conflict, a modulus
+// is 0, or the lcm doesn't fit in u64.
+pub fn crt(congruences: impl IntoIterator<Item = (u64, u64)>) -> Option<(u64, u64)> {
+    congruences
+        .into_iter()
+        .try_fold((0_u64, 1_u64), |(x, m), (r, n)| {
+            if n == 0 {
+                return None;
+            }
+            // x + m*k = r (mod n)  =>  m*k = r - x (mod n)
+            let (g, p, _) = ext_gcd(m as i128, n as i128);
+            let diff = (r % n) as i128 - x as i128;
+            if diff % g != 0 {
+                return None;
+            }
+            let step = n / g as u64;
+            // Checked before anything is multiplied, so a too-big lcm can't overflow on the way
+            let l = m.checked_mul(step)?;
+            // Both factors are below step, so their product fits in u128
+            let step = step as u128;
+            let k = (diff / g).rem_euclid(step as i128) as u128
+                * p.rem_euclid(step as i128) as u128
+                % step;
+            // k < step, so x + m*k < m*step = l
+            Some((x + m * k as u64, l))
+        })
+}
+
+// Largest r with r*r <= n.
+pub fn isqrt(n: u64) -> u64 {
+    n.isqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(gcd_all([12, 18, 8]), 2);
+        assert_eq!(gcd_all([]), 0);
+
+        assert_eq!(checked_lcm(4, 6), Some(12));
+        assert_eq!(checked_lcm(0, 6), Some(0));
+        assert_eq!(checked_lcm_all([2, 3, 4, 6]), Some(12));
+        assert_eq!(checked_lcm_all([]), Some(1));
+        assert_eq!(checked_lcm(u64::MAX, 2), None);
+        assert_eq!(checked_lcm(u64::MAX, u64::MAX), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_pow_mod() {
+        assert_eq!(pow_mod(2, 10, 1000), 24);
+        assert_eq!(pow_mod(3, 0, 7), 1);
+        assert_eq!(pow_mod(3, 0, 1), 0);
+        // Would overflow without widening
+        assert_eq!(pow_mod(u64::MAX - 1, 2, u64::MAX), 1);
+    }
+
+    #[test]
+    fn test_inv_mod() {
+        assert_eq!(inv_mod(3, 7), Some(5));
+        assert_eq!(inv_mod(10, 17), Some(12));
+        assert_eq!(inv_mod(2, 4), None);
+        assert_eq!(inv_mod(1, 0), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // Not coprime, but consistent
+        assert_eq!(crt([(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt([(1, 4), (2, 6)]), None);
+        assert_eq!(crt([]), Some((0, 1)));
+        assert_eq!(crt([(1, 0)]), None);
+        assert_eq!(crt([(0, u64::MAX), (1, 2)]), None);
+
+        // Moduli near u64::MAX, where the lcm only just fits or doesn't
+        assert_eq!(crt([(1, 2), (5, u64::MAX - 58)]), None);
+        let (a, b) = (3, u64::MAX / 3);
+        let (x, l) = crt([(1, a), (b - 1, b)]).unwrap();
+        assert_eq!((x % a, x % b, l), (1, b - 1, a * b));
+        let (a, b) = (u32::MAX as u64 - 4, u32::MAX as u64 - 16);
+        let (x, l) = crt([(a - 1, a), (7, b)]).unwrap();
+        assert_eq!((x % a, x % b, l), (a - 1, 7, a * b));
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for (n, want) in [
+            (0, 0),
+            (1, 1),
+            (3, 1),
+            (4, 2),
+            (99, 9),
+            (u64::MAX, u32::MAX as u64),
+        ] {
+            assert_eq!(isqrt(n), want, "{n}")
+        }
+    }
+}
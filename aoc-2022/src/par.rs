@@ -0,0 +1,67 @@
+// Independent work, spread over rayon's thread pool with the `parallel` feature and run in order
+// on the current thread without it. Results always come back in input order, so output is the
+// same either way.
+pub const ENABLED: bool = cfg!(feature = "parallel");
+
+pub fn join<A: Send, B: Send>(
+    a: impl FnOnce() -> A + Send,
+    b: impl FnOnce() -> B + Send,
+) -> (A, B) {
+    #[cfg(feature = "parallel")]
+    return rayon::join(a, b);
+    #[cfg(not(feature = "parallel"))]
+    return (a(), b());
+}
+
+pub fn map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync + Send) -> Vec<U> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    items.iter().map(f).collect()
+}
+
+// The result for the earliest item that has one, even if a later item finishes first.
+pub fn find_map_first<T: Sync, U: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> Option<U> + Sync + Send,
+) -> Option<U> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().find_map_first(f)
+    }
+    #[cfg(not(feature = "parallel"))]
+    items.iter().find_map(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        let items: Vec<u64> = (0..1000).collect();
+        assert_eq!(
+            map(&items, |n| n * n),
+            items.iter().map(|n| n * n).collect::<Vec<_>>()
+        );
+        assert_eq!(join(|| 1, || "two"), (1, "two"));
+    }
+
+    #[test]
+    fn test_find_map_first() {
+        let items: Vec<u32> = (0..1000).collect();
+        // Later matches are cheaper, so with threads they're likely to be found first
+        let slow_small = |n: &u32| {
+            if *n < 10 {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            (n % 7 == 3).then_some(*n)
+        };
+        assert_eq!(find_map_first(&items, slow_small), Some(3));
+        assert_eq!(find_map_first(&items, |_| None::<u32>), None);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::{cli, grid::Grid};
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+pub const RED: Rgb = [220, 40, 40];
+
+// Where `--render <path>` asked for an image to go, if anywhere.
+pub fn render_arg() -> Option<PathBuf> {
+    cli::arg_value("--render").map(PathBuf::from)
+}
+
+// An indexed set of colours, e.g. one per height or per cell state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<Rgb>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<Rgb>) -> Self {
+        Self { colors }
+    }
+
+    // `n` colours evenly spaced between `from` and `to`, inclusive.
+    pub fn gradient(from: Rgb, to: Rgb, n: usize) -> Self {
+        let colors = (0..n)
+            .map(|i| {
+                let t = if n > 1 { i as f32 / (n - 1) as f32 } else { 0. };
+                [0, 1, 2]
+                    .map(|c| (from[c] as f32 + (to[c] as f32 - from[c] as f32) * t).round() as u8)
+            })
+            .collect();
+        Self { colors }
+    }
+
+    // Out of range indices clamp to the last colour.
+    pub fn get(&self, i: usize) -> Rgb {
+        self.colors
+            .get(i)
+            .or(self.colors.last())
+            .copied()
+            .unwrap_or(BLACK)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> Rgb) -> Self {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn from_grid<T>(grid: &Grid<T>, mut f: impl FnMut(&T) -> Rgb) -> Self {
+        Self::from_fn(grid.width(), grid.height(), |x, y| {
+            grid.get(x, y).map_or(BLACK, &mut f)
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    // Out of bounds writes are ignored so callers can draw without clipping.
+    pub fn set(&mut self, x: usize, y: usize, c: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = c
+        }
+    }
+
+    // Each pixel becomes a `factor` x `factor` block.
+    pub fn scaled(&self, factor: usize) -> Self {
+        Self::from_fn(self.width * factor, self.height * factor, |x, y| {
+            self.pixels[(y / factor) * self.width + x / factor]
+        })
+    }
+
+    pub fn write_ppm(&self, mut w: impl Write) -> Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.pixels.concat())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "render"))]
+    pub fn write_png(&self, _: impl Write) -> Result<()> {
+        bail!("PNG output needs the `render` feature, or save as .ppm")
+    }
+
+    #[cfg(feature = "render")]
+    pub fn write_png(&self, w: impl Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(w, self.width.try_into()?, self.height.try_into()?);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels.concat())?;
+        Ok(())
+    }
+
+    // Picks the format from the extension: .png or .ppm
+    pub fn save(&self, path: &Path) -> Result<()> {
+        ensure!(
+            self.width > 0 && self.height > 0,
+            "can't save an empty {}x{} image",
+            self.width,
+            self.height
+        );
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let f = File::create(path).with_context(|| format!("creating {path:?}"))?;
+        let w = BufWriter::new(f);
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => self.write_png(w),
+            "ppm" => self.write_ppm(w),
+            _ => bail!("unknown image format {ext:?}, expected png or ppm"),
+        }
+        .with_context(|| format!("writing {path:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradient() {
+        let p = Palette::gradient(BLACK, [100, 200, 250], 3);
+        assert_eq!(p.get(0), BLACK);
+        assert_eq!(p.get(1), [50, 100, 125]);
+        assert_eq!(p.get(2), [100, 200, 250]);
+        assert_eq!(p.get(99), [100, 200, 250]);
+    }
+
+    #[test]
+    fn test_ppm() {
+        let g = Grid::from_rows(vec![vec![true, false]]).unwrap();
+        let img = Image::from_grid(&g, |b| if *b { WHITE } else { BLACK }).scaled(2);
+        assert_eq!((img.width(), img.height()), (4, 2));
+
+        let mut out = Vec::new();
+        img.write_ppm(&mut out).unwrap();
+        let mut want = b"P6\n4 2\n255\n".to_vec();
+        for _ in 0..2 {
+            want.extend([255; 6]);
+            want.extend([0; 6]);
+        }
+        assert_eq!(out, want);
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_png() {
+        let img = Image::from_fn(3, 2, |x, y| [x as u8, y as u8, 0]);
+        let mut out = Vec::new();
+        img.write_png(&mut out).unwrap();
+
+        let decoder = png::Decoder::new(&out[..]);
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&buf[..info.buffer_size()], &img.pixels.concat()[..]);
+    }
+}
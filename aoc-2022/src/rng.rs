@@ -0,0 +1,35 @@
+// Knuth's MMIX constants. Good enough to pick positions and generate test inputs, and no
+// dependency needed. The same seed always gives the same sequence.
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    // In 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_below() {
+        let draw = |seed| {
+            let mut rng = Lcg::new(seed);
+            (0..100).map(|_| rng.below(6)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(1), draw(1));
+        assert_ne!(draw(1), draw(2));
+        assert!(draw(1).iter().all(|&x| x < 6));
+        assert!((0..6).all(|x| draw(1).contains(&x)));
+    }
+}
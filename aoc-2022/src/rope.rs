@@ -0,0 +1,309 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+// A sequence kept as a treap keyed by position, so splitting it in two, joining two and reversing
+// a whole one take O(log n) expected time rather than O(n). Reversal is lazy: a flag on a node
+// says its subtree is backwards, and is only pushed down to the children when something has to
+// look inside.
+#[derive(Clone)]
+pub struct Rope<T> {
+    root: Link<T>,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+#[derive(Clone)]
+struct Node<T> {
+    value: T,
+    priority: u64,
+    size: usize,
+    reversed: bool,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+// Priorities only need to look random, and be comparable between ropes that get joined
+fn priority() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    // splitmix64's finaliser
+    let mut x = NEXT.fetch_add(0x9e3779b97f4a7c15, Relaxed);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |n| n.size)
+}
+
+impl<T> Node<T> {
+    fn push_down(&mut self) {
+        if self.reversed {
+            std::mem::swap(&mut self.left, &mut self.right);
+            for child in [&mut self.left, &mut self.right].into_iter().flatten() {
+                child.reversed ^= true;
+            }
+            self.reversed = false;
+        }
+    }
+
+    fn update(&mut self) {
+        self.size = size(&self.left) + 1 + size(&self.right);
+    }
+}
+
+// The first `k` items, and the rest
+fn split<T>(link: Link<T>, k: usize) -> (Link<T>, Link<T>) {
+    let Some(mut n) = link else {
+        return (None, None);
+    };
+    n.push_down();
+    let left = size(&n.left);
+    if k <= left {
+        let (a, b) = split(n.left.take(), k);
+        n.left = b;
+        n.update();
+        (a, Some(n))
+    } else {
+        let (a, b) = split(n.right.take(), k - left - 1);
+        n.right = a;
+        n.update();
+        (Some(n), b)
+    }
+}
+
+fn merge<T>(a: Link<T>, b: Link<T>) -> Link<T> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority > b.priority {
+                a.push_down();
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.push_down();
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+impl<T> Rope<T> {
+    pub fn new() -> Self {
+        Self { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn push(&mut self, value: T) {
+        let node = Node {
+            value,
+            priority: priority(),
+            size: 1,
+            reversed: false,
+            left: None,
+            right: None,
+        };
+        self.root = merge(self.root.take(), Some(Box::new(node)));
+    }
+
+    // Like Vec::split_off, leaving the first `at` items and returning the rest
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len(), "split at {at} of {}", self.len());
+        let (a, b) = split(self.root.take(), at);
+        self.root = a;
+        Self { root: b }
+    }
+
+    // Like Vec::append, moving all of `other` onto the end
+    pub fn append(&mut self, other: &mut Self) {
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    pub fn reverse(&mut self) {
+        if let Some(n) = &mut self.root {
+            n.reversed ^= true;
+        }
+    }
+
+    // Follows the reversed flags down without pushing them, so it only needs &self
+    pub fn get(&self, mut i: usize) -> Option<&T> {
+        let mut link = &self.root;
+        let mut flip = false;
+        while let Some(n) = link {
+            flip ^= n.reversed;
+            let (left, right) = if flip {
+                (&n.right, &n.left)
+            } else {
+                (&n.left, &n.right)
+            };
+            match i.cmp(&size(left)) {
+                Ordering::Less => link = left,
+                Ordering::Equal => return Some(&n.value),
+                Ordering::Greater => {
+                    i -= size(left) + 1;
+                    link = right;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.descend(&self.root, false);
+        iter
+    }
+}
+
+// In order, with each node on the stack until its value is returned
+pub struct Iter<'a, T> {
+    stack: Vec<(&'a Node<T>, bool)>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn descend(&mut self, mut link: &'a Link<T>, mut flip: bool) {
+        while let Some(n) = link {
+            flip ^= n.reversed;
+            self.stack.push((n, flip));
+            link = if flip { &n.right } else { &n.left };
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let (n, flip) = self.stack.pop()?;
+        self.descend(if flip { &n.left } else { &n.right }, flip);
+        Some(&n.value)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Rope<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> Default for Rope<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for Rope<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut rope = Self::new();
+        rope.extend(iter);
+        rope
+    }
+}
+
+impl<T> Extend<T> for Rope<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value)
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Rope<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Rope<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Rope<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Lcg;
+
+    #[test]
+    fn test_basics() {
+        let mut r: Rope<_> = (0..10).collect();
+        assert_eq!(r.len(), 10);
+        assert_eq!(r.last(), Some(&9));
+        let mut top = r.split_off(7);
+        assert_eq!(top.iter().copied().collect::<Vec<_>>(), [7, 8, 9]);
+        top.reverse();
+        r.append(&mut top);
+        assert!(top.is_empty());
+        assert_eq!(
+            r.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 6, 9, 8, 7]
+        );
+        assert_eq!((r.get(7), r.get(10)), (Some(&9), None));
+        assert_eq!(Rope::<u8>::new().last(), None);
+    }
+
+    // Random splits, reversals and joins give the same as doing them on a Vec
+    #[test]
+    fn test_against_vec() {
+        let mut rng = Lcg::new(7);
+        let mut below = |n| rng.below(n);
+        let mut vecs: Vec<Vec<usize>> = (0..5).map(|i| (i * 100..i * 100 + 50).collect()).collect();
+        let mut ropes: Vec<Rope<usize>> =
+            vecs.iter().map(|v| v.iter().copied().collect()).collect();
+        for _ in 0..2000 {
+            let (src, dest) = (below(5), below(5));
+            let at = below(vecs[src].len() + 1);
+            let reverse = below(2) == 0;
+
+            let mut v = vecs[src].split_off(at);
+            let mut r = ropes[src].split_off(at);
+            if reverse {
+                v.reverse();
+                r.reverse();
+            }
+            vecs[dest].append(&mut v);
+            ropes[dest].append(&mut r);
+
+            let i = below(vecs[dest].len() + 1);
+            assert_eq!(ropes[dest].get(i), vecs[dest].get(i));
+            assert_eq!(ropes[dest].last(), vecs[dest].last());
+        }
+        for (v, r) in vecs.iter().zip(&ropes) {
+            assert_eq!(r.len(), v.len());
+            assert_eq!(r.iter().collect::<Vec<_>>(), v.iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_deep() {
+        // Building one item at a time still gives a tree shallow enough to drop and walk
+        let mut r: Rope<u32> = (0..1_000_000).collect();
+        r.reverse();
+        assert_eq!(r.iter().next(), Some(&999_999));
+        assert_eq!(r.get(1), Some(&999_998));
+    }
+}
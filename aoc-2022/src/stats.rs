@@ -0,0 +1,166 @@
+use std::{cmp::Reverse, collections::BinaryHeap, ops::Add};
+
+// The k largest items pushed, remembering where each was in the input. Memory is O(k) however
+// many items are pushed. Between equal items the earlier one wins, so results are stable.
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    k: usize,
+    pushed: usize,
+    // Min-heap of the best so far, worst on top. Reversing the index puts later duplicates on
+    // top, so they're the ones evicted.
+    heap: BinaryHeap<Reverse<(T, Reverse<usize>)>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            pushed: 0,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        let entry = Reverse((item, Reverse(self.pushed)));
+        self.pushed += 1;
+        if self.heap.len() < self.k {
+            self.heap.push(entry);
+        } else if let Some(mut worst) = self.heap.peek_mut() {
+            if entry < *worst {
+                *worst = entry;
+            }
+        }
+    }
+
+    // (index, item) pairs, largest first. Fewer than k if fewer were pushed.
+    pub fn into_sorted_vec(self) -> Vec<(usize, T)> {
+        // Ascending order of Reverse is descending order of the items
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((item, Reverse(i)))| (i, item))
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<T> for TopK<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item)
+        }
+    }
+}
+
+pub fn top_k<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<(usize, T)> {
+    let mut top = TopK::new(k);
+    top.extend(items);
+    top.into_sorted_vec()
+}
+
+pub trait ToF64 {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_to_f64 {
+    ($($t:ty),*) => {$(
+        impl ToF64 for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    )*};
+}
+
+impl_to_f64!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// Running count, min, max and sum, for when collecting everything just to summarise it is a waste.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats<T> {
+    pub count: usize,
+    pub min: Option<T>,
+    pub max: Option<T>,
+    pub sum: T,
+}
+
+impl<T: Default> Default for Stats<T> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            min: None,
+            max: None,
+            sum: T::default(),
+        }
+    }
+}
+
+impl<T: Copy + Ord + Add<Output = T>> Stats<T> {
+    pub fn push(&mut self, v: T) {
+        self.count += 1;
+        self.min = Some(self.min.map_or(v, |m| m.min(v)));
+        self.max = Some(self.max.map_or(v, |m| m.max(v)));
+        self.sum = self.sum + v;
+    }
+
+    // None if nothing was pushed.
+    pub fn mean(&self) -> Option<f64>
+    where
+        T: ToF64,
+    {
+        (self.count > 0).then(|| self.sum.to_f64() / self.count as f64)
+    }
+}
+
+impl<T: Copy + Ord + Add<Output = T>> Extend<T> for Stats<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for v in iter {
+            self.push(v)
+        }
+    }
+}
+
+impl<T: Copy + Ord + Add<Output = T> + Default> FromIterator<T> for Stats<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut s = Self::default();
+        s.extend(iter);
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k() {
+        assert_eq!(top_k([5, 1, 9, 3, 7], 3), [(2, 9), (4, 7), (0, 5)]);
+        assert_eq!(top_k([5, 1], 3), [(0, 5), (1, 1)]);
+        assert_eq!(top_k([5, 1], 0), []);
+        assert_eq!(top_k(Vec::<u8>::new(), 2), []);
+    }
+
+    #[test]
+    fn test_top_k_stable() {
+        // Earlier duplicates win, both when choosing and ordering
+        assert_eq!(top_k([1, 4, 2, 4, 4, 3], 2), [(1, 4), (3, 4)]);
+        assert_eq!(top_k([4, 4, 4], 3), [(0, 4), (1, 4), (2, 4)]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let s: Stats<u32> = [3, 1, 4, 1, 5].into_iter().collect();
+        assert_eq!(
+            s,
+            Stats {
+                count: 5,
+                min: Some(1),
+                max: Some(5),
+                sum: 14
+            }
+        );
+        assert_eq!(s.mean(), Some(2.8));
+
+        let empty: Stats<i64> = std::iter::empty().collect();
+        assert_eq!((empty.min, empty.max, empty.sum), (None, None, 0));
+        assert_eq!(empty.mean(), None);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::cli::arg_value;
+
+// Records spans to a file for flamegraph viewers, picking the format from the extension: a Chrome
+// trace (.json, for Perfetto or chrome://tracing) or folded stacks (.folded, for inferno or
+// flamegraph.pl). Recording needs the `trace` feature; without it asking for a trace is an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Chrome,
+    Folded,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Ok(Self::Chrome),
+            Some("folded") => Ok(Self::Folded),
+            _ => bail!(
+                "{}: trace files must end in .json or .folded",
+                path.display()
+            ),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Chrome => "json",
+            Self::Folded => "folded",
+        }
+    }
+}
+
+// The file is written when this is dropped, so keep it until the end of main.
+pub struct Guard {
+    #[cfg(feature = "trace")]
+    _chrome: Option<tracing_chrome::FlushGuard>,
+    #[cfg(feature = "trace")]
+    _folded: Option<tracing_flame::FlushGuard<std::io::BufWriter<fs::File>>>,
+}
+
+// Starts recording if `--trace <file>` was passed.
+pub fn trace_arg() -> Result<Option<Guard>> {
+    arg_value("--trace")
+        .map(|p| start(Path::new(&p)))
+        .transpose()
+}
+
+#[cfg(feature = "trace")]
+pub fn start(path: &Path) -> Result<Guard> {
+    use tracing_subscriber::prelude::*;
+
+    let mut guard = Guard {
+        _chrome: None,
+        _folded: None,
+    };
+    match Format::from_path(path)? {
+        Format::Chrome => {
+            let (layer, g) = tracing_chrome::ChromeLayerBuilder::new()
+                .file(path)
+                .include_args(true)
+                .build();
+            tracing_subscriber::registry().with(layer).try_init()?;
+            guard._chrome = Some(g);
+        }
+        Format::Folded => {
+            let (layer, g) = tracing_flame::FlameLayer::with_file(path)
+                .with_context(|| format!("creating {}", path.display()))?;
+            let layer = layer.with_threads_collapsed(true).with_file_and_line(false);
+            tracing_subscriber::registry().with(layer).try_init()?;
+            guard._folded = Some(g);
+        }
+    }
+    Ok(guard)
+}
+
+#[cfg(not(feature = "trace"))]
+pub fn start(path: &Path) -> Result<Guard> {
+    Format::from_path(path)?;
+    bail!(
+        "{}: recording a trace needs the `trace` feature",
+        path.display()
+    )
+}
+
+// Combines traces from separate processes into one file, keeping each under its own name: as a
+// process in a Chrome trace, or as the root frame of folded stacks.
+pub fn merge(parts: &[(&str, PathBuf)], out: &Path) -> Result<()> {
+    let data = match Format::from_path(out)? {
+        Format::Chrome => merge_chrome(parts)?,
+        Format::Folded => merge_folded(parts)?,
+    };
+    fs::write(out, data).with_context(|| format!("writing {}", out.display()))
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))
+}
+
+#[cfg(feature = "trace")]
+fn merge_chrome(parts: &[(&str, PathBuf)]) -> Result<String> {
+    use serde_json::{json, Value};
+
+    let mut events = Vec::new();
+    for (pid, (name, path)) in parts.iter().enumerate() {
+        let part: Vec<Value> = serde_json::from_str(&read(path)?)
+            .with_context(|| format!("parsing {}", path.display()))?;
+        events.push(json!({
+            "ph": "M",
+            "name": "process_name",
+            "pid": pid,
+            "args": { "name": name },
+        }));
+        for mut e in part {
+            e["pid"] = pid.into();
+            events.push(e);
+        }
+    }
+    Ok(serde_json::to_string(&events)?)
+}
+
+#[cfg(not(feature = "trace"))]
+fn merge_chrome(_: &[(&str, PathBuf)]) -> Result<String> {
+    bail!("merging Chrome traces needs the `trace` feature")
+}
+
+fn merge_folded(parts: &[(&str, PathBuf)]) -> Result<String> {
+    let mut folded = String::new();
+    for (name, path) in parts {
+        for l in read(path)?.lines() {
+            // Same separator as tracing-flame
+            folded += &format!("{name}; {l}\n");
+        }
+    }
+    Ok(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            Format::from_path(Path::new("a/t.json")).unwrap(),
+            Format::Chrome
+        );
+        assert_eq!(
+            Format::from_path(Path::new("t.folded")).unwrap(),
+            Format::Folded
+        );
+        assert!(Format::from_path(Path::new("t.txt")).is_err());
+        assert!(Format::from_path(Path::new("json")).is_err());
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-trace-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_merge() {
+        let dir = temp_dir("folded");
+        let write = |name: &str, data: &str| {
+            let p = dir.join(name);
+            fs::write(&p, data).unwrap();
+            p
+        };
+
+        let folded = [
+            ("day01", write("1.folded", "solve; part1 10\nsolve 3\n")),
+            ("day02", write("2.folded", "solve 5\n")),
+        ];
+        let out = dir.join("all.folded");
+        merge(&folded, &out).unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap(),
+            "day01; solve; part1 10\nday01; solve 3\nday02; solve 5\n"
+        );
+        assert!(merge(&folded, &dir.join("all.txt")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_merge_chrome() {
+        use serde_json::{json, Value};
+
+        let dir = temp_dir("chrome");
+        let write = |name: &str, data: &str| {
+            let p = dir.join(name);
+            fs::write(&p, data).unwrap();
+            p
+        };
+
+        let chrome = [
+            (
+                "day01",
+                write("1.json", r#"[{"ph":"B","pid":1,"name":"solve"}]"#),
+            ),
+            ("day02", write("2.json", "[\n]")),
+        ];
+        let out = dir.join("all.json");
+        merge(&chrome, &out).unwrap();
+        let got: Value = serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(
+            got,
+            json!([
+                {"ph": "M", "name": "process_name", "pid": 0, "args": {"name": "day01"}},
+                {"ph": "B", "pid": 0, "name": "solve"},
+                {"ph": "M", "name": "process_name", "pid": 1, "args": {"name": "day02"}},
+            ])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(not(feature = "trace"))]
+    #[test]
+    fn test_needs_feature() {
+        assert!(start(Path::new("t.json")).is_err());
+        assert!(merge(&[], Path::new("t.json")).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+// Counts allocations made by the current thread, so the test harness's other threads don't count.
+struct Counting;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocs<T>(f: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCS.with(Cell::get);
+    let out = f();
+    (ALLOCS.with(Cell::get) - before, out)
+}
+
+#[test]
+fn core_solvers_dont_allocate() {
+    use aoc_2022_core::{day01, day02, day03, day04, day06};
+
+    let (n, r) = allocs(|| day01::solve(include_bytes!("../data/challenge/day01.txt")));
+    assert_eq!((n, r.is_ok()), (0, true), "day01");
+    let (n, r) = allocs(|| day02::solve(include_bytes!("../data/challenge/day02.txt")));
+    assert_eq!((n, r.is_ok()), (0, true), "day02");
+    let (n, r) = allocs(|| day03::solve(include_bytes!("../data/challenge/day03.txt")));
+    assert_eq!((n, r.is_ok()), (0, true), "day03");
+    let (n, r) = allocs(|| day04::solve(include_bytes!("../data/challenge/day04.txt")));
+    assert_eq!((n, r.is_ok()), (0, true), "day04");
+    let (n, r) = allocs(|| day06::solve(include_bytes!("../data/challenge/day06.txt")));
+    assert_eq!((n, r.is_ok()), (0, true), "day06");
+
+    // Errors don't allocate either
+    let (n, r) = allocs(|| day04::solve(b"1-2,3-x"));
+    assert_eq!((n, r.is_err()), (0, true), "day04 error");
+}
+
+#[test]
+fn counter_works() {
+    let (n, v) = allocs(|| vec![1_u8; 10]);
+    assert_eq!((n, v.len()), (1, 10));
+}